@@ -0,0 +1,105 @@
+use std::{io, path::Path, process::ExitCode};
+
+use gnyprland_relay::message::{IpcMessage, IpcResponse, Status};
+
+use crate::socket;
+
+/// Exit status used when no running bar could be reached
+pub const EXIT_UNREACHABLE: u8 = 3;
+
+pub async fn request(socket: &Path, message: IpcMessage) -> io::Result<IpcResponse> {
+    let mut stream = socket::connect(socket).await?;
+
+    socket::send(&mut stream, message.to_string()).await?;
+    debug!("Sent message: {message:?}");
+
+    let response = socket::receive(&mut stream).await?;
+    serde_json::from_str(&response).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Print the running bar's recent logs, then keep printing new ones until
+/// interrupted when following.
+pub fn logs(socket: &Path, follow: bool) -> ExitCode {
+    smol::block_on(async {
+        let mut stream = match socket::connect(socket).await {
+            Ok(stream) => stream,
+            Err(e) => {
+                error!("Failed to talk to {}: {e}", socket.display());
+                return ExitCode::from(EXIT_UNREACHABLE);
+            }
+        };
+
+        let message = IpcMessage::Logs { follow };
+
+        if let Err(e) = socket::send(&mut stream, message.to_string()).await {
+            error!("Failed to talk to {}: {e}", socket.display());
+            return ExitCode::from(EXIT_UNREACHABLE);
+        }
+
+        // the bar hangs up once there is nothing more to send
+        while let Ok(response) = socket::receive(&mut stream).await {
+            match serde_json::from_str(&response) {
+                Ok(IpcResponse::Logs(records)) => {
+                    for record in records {
+                        println!("{record}");
+                    }
+                }
+                Ok(IpcResponse::Error(e)) => {
+                    eprintln!("error: {e}");
+                    return ExitCode::FAILURE;
+                }
+                Ok(response) => warn!("Unexpected response: {response:?}"),
+                Err(e) => {
+                    error!("Invalid response: {e}");
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+
+        ExitCode::SUCCESS
+    })
+}
+
+fn print_status(status: &Status, json: bool) {
+    if json {
+        println!("{}", serde_json::to_string_pretty(status).unwrap());
+        return;
+    }
+
+    println!("pid      {}", status.pid);
+    println!("version  {}", status.version);
+    println!(
+        "build    {}",
+        if status.debug { "debug" } else { "release" }
+    );
+    println!("overlay  {}", status.overlay.as_deref().unwrap_or("none"));
+
+    if let Some(e) = &status.config_error {
+        println!("config   {e}");
+    }
+}
+
+/// Send `message` to the running bar and print its response. The exit code is
+/// a failure whenever the bar answers with an error.
+pub fn run(socket: &Path, message: IpcMessage, json: bool) -> ExitCode {
+    let response = match smol::block_on(request(socket, message)) {
+        Ok(response) => response,
+        Err(e) => {
+            error!("Failed to talk to {}: {e}", socket.display());
+            return ExitCode::from(EXIT_UNREACHABLE);
+        }
+    };
+
+    match &response {
+        IpcResponse::Ok => {}
+        IpcResponse::Status(status) => print_status(status, json),
+        IpcResponse::Logs(records) => records.iter().for_each(|r| println!("{r}")),
+        IpcResponse::Error(e) => eprintln!("error: {e}"),
+    }
+
+    if response.is_ok() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,77 @@
+use std::{
+    fs::{self, File, OpenOptions, TryLockError},
+    io::{self, Write},
+    os::unix::fs::OpenOptionsExt,
+    path::{Path, PathBuf},
+    process,
+    time::{Duration, Instant},
+};
+
+use smol::Timer;
+
+use crate::socket;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The lock file sits next to the socket, so every socket path gets its own
+/// single instance.
+pub fn lock_path(socket: &Path) -> PathBuf {
+    socket.with_extension("lock")
+}
+
+/// An exclusive advisory lock held for as long as the bar is running. The
+/// kernel releases it when the process exits, however that happens.
+pub struct InstanceLock {
+    _file: File,
+}
+
+impl InstanceLock {
+    /// Take the lock without blocking, returning `None` if another instance
+    /// already holds it.
+    pub fn try_acquire(path: &Path) -> io::Result<Option<Self>> {
+        if let Some(dir) = path.parent() {
+            socket::prepare_dir(dir)?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .mode(0o600)
+            .open(path)?;
+
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => return Ok(None),
+            Err(TryLockError::Error(e)) => return Err(e),
+        }
+
+        // record our pid for diagnostics, the lock itself is what counts
+        file.set_len(0)?;
+        write!(file, "{}", process::id())?;
+
+        Ok(Some(Self { _file: file }))
+    }
+
+    /// Keep retrying [`InstanceLock::try_acquire`] until `timeout` elapses.
+    pub async fn acquire_timeout(path: &Path, timeout: Duration) -> io::Result<Option<Self>> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            if let Some(lock) = Self::try_acquire(path)? {
+                return Ok(Some(lock));
+            }
+
+            if Instant::now() >= deadline {
+                return Ok(None);
+            }
+
+            Timer::after(POLL_INTERVAL).await;
+        }
+    }
+
+    /// The pid of the process holding the lock, if it wrote one.
+    pub fn holder(path: &Path) -> Option<u32> {
+        fs::read_to_string(path).ok()?.trim().parse().ok()
+    }
+}
@@ -0,0 +1,308 @@
+use std::{
+    collections::VecDeque,
+    env,
+    ffi::OsString,
+    fs::{self, File, OpenOptions},
+    io::{self, IsTerminal, Write},
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Mutex, OnceLock, RwLock},
+};
+
+use gnyprland_config::{LogConfig, LogDirectives, error::DirectivesError};
+use gnyprland_relay::message::LogRecord;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use simple_logger::SimpleLogger;
+use smol::channel::{self, Receiver, Sender};
+use time::{
+    OffsetDateTime, UtcOffset, format_description::BorrowedFormatItem, macros::format_description,
+};
+
+/// How many records `gnyprland logs` can look back on
+const BUFFER_SIZE: usize = 1000;
+
+const TIMESTAMP: &[BorrowedFormatItem<'static>] =
+    format_description!("[year]-[month]-[day] [hour]:[minute]:[second].[subsecond digits:3]");
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+/// Logs for journald on stderr: every line carries an `sd-daemon` priority
+/// prefix, and colors and timestamps are left out since the journal keeps its
+/// own.
+struct JournalLogger;
+
+impl Log for JournalLogger {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let priority = match record.level() {
+            Level::Error => 3,
+            Level::Warn => 4,
+            Level::Info => 6,
+            Level::Debug | Level::Trace => 7,
+        };
+
+        let message = record.args().to_string();
+        let mut stderr = io::stderr().lock();
+
+        for line in message.lines() {
+            let _ = writeln!(stderr, "<{priority}>{}: {line}", record.target());
+        }
+    }
+
+    fn flush(&self) {
+        let _ = io::stderr().flush();
+    }
+}
+
+/// Whether stderr is connected to the journal, which systemd tells through
+/// `$JOURNAL_STREAM` holding the device and inode of that stream
+fn logs_to_journal() -> bool {
+    let Some(stream) = env::var_os("JOURNAL_STREAM") else {
+        return false;
+    };
+
+    fs::metadata("/dev/stderr")
+        .is_ok_and(|meta| stream.to_str() == Some(&format!("{}:{}", meta.dev(), meta.ino())))
+}
+
+/// A log file that is moved to `<path>.1` once it grows past `max_size`,
+/// shifting older files up to `<path>.<keep>`.
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_size: u64,
+    keep: usize,
+}
+
+impl LogFile {
+    fn open(config: &LogConfig, path: &Path) -> io::Result<Self> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            size: file.metadata()?.len(),
+            file,
+            max_size: config.max_size,
+            keep: config.keep,
+        })
+    }
+
+    fn rotated(&self, n: usize) -> PathBuf {
+        let mut path = OsString::from(&self.path);
+        path.push(format!(".{n}"));
+        path.into()
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        for n in (1..self.keep).rev() {
+            let from = self.rotated(n);
+
+            if from.exists() {
+                fs::rename(from, self.rotated(n + 1))?;
+            }
+        }
+
+        if self.keep > 0 {
+            fs::rename(&self.path, self.rotated(1))?;
+        }
+
+        self.file = File::create(&self.path)?;
+        self.size = 0;
+
+        Ok(())
+    }
+
+    fn write(&mut self, line: &str) -> io::Result<()> {
+        let len = line.len() as u64 + 1;
+
+        if self.size > 0 && self.size + len > self.max_size {
+            self.rotate()?;
+        }
+
+        writeln!(self.file, "{line}")?;
+        self.size += len;
+
+        Ok(())
+    }
+}
+
+/// Filters records by the current directives before handing them to the
+/// terminal or journal, and keeps the most recent ones around for `logs`.
+struct Logger {
+    output: Box<dyn Log>,
+    offset: UtcOffset,
+
+    /// The level of the build, used where no directive applies
+    base: LevelFilter,
+    /// What `log-level reset` goes back to
+    initial: RwLock<LogDirectives>,
+    directives: RwLock<LogDirectives>,
+
+    buffer: Mutex<VecDeque<LogRecord>>,
+    followers: Mutex<Vec<Sender<LogRecord>>>,
+    file: Mutex<Option<LogFile>>,
+}
+
+impl Logger {
+    fn level(&self, target: &str) -> LevelFilter {
+        self.directives
+            .read()
+            .unwrap()
+            .level(target)
+            .unwrap_or(self.base)
+    }
+
+    fn update_max_level(&self) {
+        let directives = self.directives.read().unwrap();
+        let default = directives.default.unwrap_or(self.base);
+
+        log::set_max_level(directives.max().map_or(default, |max| max.max(default)));
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        self.output.log(record);
+
+        let time = OffsetDateTime::now_utc().to_offset(self.offset);
+        let record = LogRecord {
+            time: time.format(TIMESTAMP).unwrap_or_default(),
+            level: record.level().to_string(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+        };
+
+        if let Some(file) = self.file.lock().unwrap().as_mut() {
+            // there is nowhere left to report this to
+            let _ = file.write(&record.to_string());
+        }
+
+        // same order as `subscribe`, which needs both at once
+        let mut buffer = self.buffer.lock().unwrap();
+
+        // followers that hung up are dropped here
+        self.followers
+            .lock()
+            .unwrap()
+            .retain(|tx| tx.try_send(record.clone()).is_ok());
+
+        if buffer.len() == BUFFER_SIZE {
+            buffer.pop_front();
+        }
+
+        buffer.push_back(record);
+    }
+
+    fn flush(&self) {
+        self.output.flush();
+
+        if let Some(file) = self.file.lock().unwrap().as_mut() {
+            let _ = file.file.flush();
+        }
+    }
+}
+
+fn logger() -> &'static Logger {
+    LOGGER.get().expect("logging is not initialized")
+}
+
+pub fn init(level: LevelFilter) {
+    // the filtering happens in `Logger`, so let everything through here
+    let output: Box<dyn Log> = if logs_to_journal() {
+        Box::new(JournalLogger)
+    } else {
+        Box::new(
+            SimpleLogger::new()
+                .with_colors(io::stdout().is_terminal())
+                .with_threads(cfg!(debug_assertions))
+                .with_source_location(cfg!(debug_assertions))
+                .with_local_timestamps()
+                .with_timestamp_format(TIMESTAMP)
+                .with_level(LevelFilter::Trace),
+        )
+    };
+
+    let logger = LOGGER.get_or_init(|| Logger {
+        output,
+        // looking up the offset is only sound while we're single-threaded
+        offset: UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC),
+        base: level,
+        initial: RwLock::default(),
+        directives: RwLock::default(),
+        buffer: Mutex::new(VecDeque::with_capacity(BUFFER_SIZE)),
+        followers: Mutex::default(),
+        file: Mutex::default(),
+    });
+
+    log::set_logger(logger).unwrap();
+    logger.update_max_level();
+}
+
+/// Apply the `[log]` section of the config, once it has been loaded
+pub fn configure(config: &LogConfig) {
+    let logger = logger();
+
+    *logger.initial.write().unwrap() = config.level.clone();
+    *logger.directives.write().unwrap() = config.level.clone();
+    logger.update_max_level();
+
+    let Some(path) = &config.file else {
+        return;
+    };
+
+    match LogFile::open(config, path) {
+        Ok(file) => *logger.file.lock().unwrap() = Some(file),
+        Err(e) => error!("Failed to open log file {}: {e}", path.display()),
+    }
+}
+
+/// Apply `directives` on top of the current ones, or go back to those from the
+/// config for `reset`
+pub fn set_level(directives: &str) -> Result<(), DirectivesError> {
+    let logger = logger();
+
+    if directives == "reset" {
+        let initial = logger.initial.read().unwrap().clone();
+        *logger.directives.write().unwrap() = initial;
+    } else {
+        let directives = LogDirectives::from_str(directives)?;
+        logger.directives.write().unwrap().merge(directives);
+    }
+
+    logger.update_max_level();
+
+    // logging takes the directives too, so let go of them first
+    let current = logger.directives.read().unwrap().to_string();
+    info!("Log levels are now {current}");
+
+    Ok(())
+}
+
+/// The buffered records, along with a channel for every record after them
+pub fn subscribe() -> (Vec<LogRecord>, Receiver<LogRecord>) {
+    let logger = logger();
+    let (tx, rx) = channel::unbounded();
+
+    // hold the buffer while subscribing, so no record falls in between
+    let buffer = logger.buffer.lock().unwrap();
+    logger.followers.lock().unwrap().push(tx);
+
+    (buffer.iter().cloned().collect(), rx)
+}
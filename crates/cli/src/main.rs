@@ -1,26 +1,30 @@
 #![feature(cfg_select)]
 
 extern crate clap;
+extern crate gnyprland_config;
 extern crate simple_logger;
 #[macro_use]
 extern crate log;
-extern crate ctrlc;
 extern crate gnyprland_ui;
 extern crate smol;
 
-use std::{env, error::Error, mem, process, str::FromStr};
+mod client;
+mod instance;
+mod logging;
+mod server;
+mod socket;
+
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    str::FromStr,
+};
 
-use clap::Parser;
-use gnyprland_relay::message::{IpcMessage, IpcResponse};
+use clap::{Parser, Subcommand};
+use gnyprland_config::{Config, error::ConfigError};
+use gnyprland_relay::message::IpcMessage;
 use log::LevelFilter;
-use simple_logger::SimpleLogger;
-use smol::{
-    fs,
-    io::{self, AsyncReadExt, AsyncWriteExt},
-    net::unix::{UnixListener, UnixStream},
-    stream::StreamExt,
-};
-use time::macros::format_description;
 
 #[cfg(debug_assertions)]
 pub const LEVEL: LevelFilter = LevelFilter::Debug;
@@ -31,163 +35,211 @@ pub const LEVEL: LevelFilter = LevelFilter::Info;
 #[derive(Clone, Debug, Parser)]
 #[command(name = "gnyprland")]
 #[command(version, about = "A Gnome-like Bar for Hyprland")]
+#[command(
+    after_help = "Commands talking to a running bar exit with 1 when it reports an error \
+                        and with 3 when it cannot be reached."
+)]
 pub struct Arguments {
-    #[arg(short)]
-    inspector: bool,
-}
+    #[command(subcommand)]
+    command: Option<Command>,
 
-pub async fn receive(stream: &mut UnixStream) -> io::Result<String> {
-    let mut length_buf = [0u8; mem::size_of::<usize>()];
-    stream.read_exact(&mut length_buf).await?;
-    let length = usize::from_be_bytes(length_buf);
+    /// Path of the IPC socket, defaults to a per-user runtime directory
+    #[arg(long, global = true)]
+    socket: Option<PathBuf>,
 
-    let mut message_buf = vec![0u8; length];
-    stream.read_exact(&mut message_buf).await?;
-
-    String::from_utf8(message_buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    /// Path of the config file, defaults to
+    /// `$XDG_CONFIG_HOME/gnyprland/config.toml`
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
 }
 
-pub async fn send(stream: &mut UnixStream, message: String) -> io::Result<()> {
-    stream.write_all(&message.len().to_be_bytes()).await?;
-    stream.write_all(message.as_bytes()).await?;
-    Ok(())
+#[derive(Clone, Debug, Subcommand)]
+pub enum Command {
+    /// Run the bar, the default when no command is given
+    Run {
+        /// Ask an already running instance to quit and take its place
+        #[arg(long)]
+        replace: bool,
+
+        /// Wait for a Hyprland instance to come up instead of failing, for
+        /// when the bar is started before the compositor
+        #[arg(long)]
+        wait_for_hyprland: bool,
+    },
+
+    /// Send a raw message to the running bar
+    Msg {
+        /// One of inspector, reload-css, quit, status, toggle, log-level, logs
+        /// or bar
+        verb: String,
+        args: Vec<String>,
+    },
+
+    /// Reload the stylesheet of the running bar
+    ReloadCss,
+
+    /// Ask the running bar to quit
+    Quit,
+
+    /// Show the state of the running bar
+    Status {
+        /// Print the status as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Toggle an overlay of the running bar, e.g. `center`
+    Toggle { overlay: String },
+
+    /// Open the GTK inspector, only available in debug builds
+    Inspector,
+
+    /// Change log levels of the running bar, e.g. `debug` or
+    /// `info gnyprland_ui::bar=trace`, with commas or spaces between
+    /// directives. `reset` goes back to the levels from the config file.
+    LogLevel {
+        #[arg(required = true)]
+        directives: Vec<String>,
+    },
+
+    /// Print the recent logs of the running bar
+    Logs {
+        /// Keep printing new logs as they come in
+        #[arg(long, short)]
+        follow: bool,
+    },
+
+    /// Change the bars of the running instance
+    Bar {
+        #[command(subcommand)]
+        command: BarCommand,
+    },
+
+    /// Check the config file for errors without starting the bar
+    CheckConfig,
 }
 
-async fn start_bar() -> Result<(), Box<dyn Error>> {
-    let (tx, rx) = gnyprland_relay::channel::<IpcMessage, IpcResponse>();
-
-    // check if gnyprland is already running
-    let mut read = fs::read_dir("/proc").await?;
-    let this_pid = process::id().to_string();
-
-    while let Some(entry) = read.next().await {
-        let filename = entry?.file_name();
-        let pid = filename.to_string_lossy();
-
-        if !pid.chars().all(|c| c.is_digit(10)) {
-            continue;
-        }
-
-        if pid == this_pid {
-            continue;
-        }
-
-        let comm = format!("/proc/{pid}/comm");
-        let contents = fs::read_to_string(&comm).await?;
-
-        if contents.trim() == "gnyprland" {
-            error!("gnyprland is already running (PID: {pid})");
-            return Ok(());
-        }
-    }
+#[derive(Clone, Debug, Subcommand)]
+pub enum BarCommand {
+    /// Change a setting of the `[bar]` config section until the config file
+    /// is reloaded, e.g. `edge left`, `size 40` or `margin.top 8`
+    Set {
+        key: String,
+        #[arg(required = true)]
+        value: Vec<String>,
+    },
+
+    /// Hide or show the bars
+    Toggle,
+}
 
-    if std::fs::exists("/tmp/gnyprland.socket")? {
-        fs::remove_file("/tmp/gnyprland.socket").await?;
+/// An explicitly given config file has to exist, the default one doesn't
+fn load_config(path: Option<&Path>) -> Result<Config, ConfigError> {
+    match path {
+        Some(path) => Config::load(path),
+        None => Config::load_or_default(&Config::default_path()),
     }
+}
 
-    let Ok(listener) = UnixListener::bind("/tmp/gnyprland.socket") else {
-        error!("Failed to bind to socket");
-        return Ok(());
-    };
+fn main() -> ExitCode {
+    logging::init(LEVEL);
 
-    smol::spawn(async move {
-        let listener = listener;
+    let cli = Arguments::parse();
+    let json = matches!(cli.command, Some(Command::Status { json: true }));
+
+    // the default socket depends on the Hyprland instance, which `run` may
+    // only find out about after waiting for it
+    let socket = || cli.socket.clone().unwrap_or_else(socket::default_path);
+    let config_path = || cli.config.clone().unwrap_or_else(Config::default_path);
+
+    let command = cli.command.clone().unwrap_or(Command::Run {
+        replace: false,
+        wait_for_hyprland: false,
+    });
+
+    let message = match command {
+        Command::Run {
+            replace,
+            wait_for_hyprland,
+        } => {
+            let config = match load_config(cli.config.as_deref()) {
+                Ok(config) => config,
+                Err(e) => {
+                    error!("{e}");
+                    return ExitCode::FAILURE;
+                }
+            };
 
-        while let Ok((mut stream, _)) = listener.accept().await {
-            debug!("Got new connection");
+            logging::configure(&config.log);
 
-            let Ok(message) = receive(&mut stream).await else {
-                error!("Failed to read message from stream");
-                continue;
-            };
+            if !server::find_hyprland(wait_for_hyprland) {
+                error!("Hyprland is not running, pass --wait-for-hyprland to wait for it");
+                return ExitCode::FAILURE;
+            }
 
-            let Ok(message) = IpcMessage::from_str(message.trim()) else {
-                error!("Failed to parse message: {message}");
-                continue;
-            };
+            // give smol some threads
+            unsafe {
+                env::set_var("SMOL_THREADS", "4");
+            }
 
-            debug!("Got message: {message:?}");
-            let Ok(res) = tx.send(message).await else {
-                error!("Failed to process message");
-                return;
-            };
+            let res = smol::block_on(server::start_bar(socket(), replace, config, config_path()));
 
-            debug!("Sending response: {res:?}");
-            let Ok(_) = send(&mut stream, res.to_string()).await else {
-                error!("Failed to write response to stream");
-                return;
+            return match res {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(e) => {
+                    error!("{e}");
+                    ExitCode::FAILURE
+                }
             };
-
-            debug!("Dropping connection");
         }
-    })
-    .detach();
-
-    ctrlc::set_handler(move || {
-        info!("Received Ctrl+C, shutting down...");
-        if let Err(e) = smol::block_on(fs::remove_file("/tmp/gnyprland.socket")) {
-            error!("Failed to remove socket file: {e}");
+        Command::Msg { verb, args } => {
+            let line = [verb].into_iter().chain(args).collect::<Vec<_>>().join(" ");
+
+            match IpcMessage::from_str(&line) {
+                // logs stream in over several responses, following or not
+                Ok(IpcMessage::Logs { follow }) => return client::logs(&socket(), follow),
+                Ok(message) => message,
+                Err(e) => {
+                    error!("{e}");
+                    return ExitCode::from(2);
+                }
+            }
         }
-        process::exit(0);
-    })?;
-
-    info!("Starting UI");
-    gnyprland_ui::start(rx);
-
-    Ok(())
-}
-
-async fn connect_to_socket() -> io::Result<UnixStream> {
-    UnixStream::connect("/tmp/gnyprland.socket").await
-}
-
-fn main() {
-    SimpleLogger::new()
-        .with_colors(true)
-        .with_threads(cfg!(debug_assertions))
-        .with_source_location(cfg!(debug_assertions))
-        .with_local_timestamps()
-        .with_timestamp_format(format_description!(
-            "[year]-[month]-[day] [hour]:[minute]:[second].[subsecond digits:3]"
-        ))
-        .with_level(LEVEL)
-        .init()
-        .unwrap();
-
-    let cli = Arguments::parse();
-
-    if cli.inspector {
-        smol::block_on(async {
-            let Ok(mut stream) = connect_to_socket().await else {
-                error!("Failed to connect to socket");
-                return;
+        Command::ReloadCss => IpcMessage::ReloadCSS,
+        Command::Quit => IpcMessage::Quit,
+        Command::Status { .. } => IpcMessage::Status,
+        Command::Toggle { overlay } => IpcMessage::Toggle(overlay),
+        Command::Inspector => IpcMessage::StartInspector,
+        Command::LogLevel { directives } => IpcMessage::LogLevel(directives.join(",")),
+        Command::Logs { follow } => return client::logs(&socket(), follow),
+        Command::Bar {
+            command: BarCommand::Set { key, value },
+        } => IpcMessage::SetBar {
+            key,
+            value: value.join(" "),
+        },
+        Command::Bar {
+            command: BarCommand::Toggle,
+        } => IpcMessage::ToggleBar,
+        Command::CheckConfig => {
+            let path = config_path();
+
+            return match load_config(cli.config.as_deref()) {
+                Ok(_) if !path.exists() => {
+                    println!("{}: not found, using the defaults", path.display());
+                    ExitCode::SUCCESS
+                }
+                Ok(_) => {
+                    println!("{}: ok", path.display());
+                    ExitCode::SUCCESS
+                }
+                Err(e) => {
+                    eprintln!("{e}");
+                    ExitCode::FAILURE
+                }
             };
+        }
+    };
 
-            const MESSAGE: IpcMessage = IpcMessage::StartInspector;
-
-            let Ok(_) = send(&mut stream, MESSAGE.to_string()).await else {
-                error!("Failed to send message to socket");
-                return;
-            };
-
-            debug!("Sent message: {MESSAGE:?}");
-
-            let Ok(response) = receive(&mut stream).await else {
-                error!("Failed to read message from socket");
-                return;
-            };
-
-            println!("{response}");
-        });
-
-        return;
-    }
-
-    // run the bar
-    // give smol some threads
-    unsafe {
-        env::set_var("SMOL_THREADS", "4");
-    }
-
-    smol::block_on(start_bar()).unwrap();
+    client::run(&socket(), message, json)
 }
@@ -0,0 +1,197 @@
+use std::{env, error::Error, io, path::PathBuf, str::FromStr, sync::Arc, thread, time::Duration};
+
+use gnyprland_config::Config;
+use gnyprland_relay::message::{IpcMessage, IpcResponse, IpcSender};
+use gnyprland_ui::systemd;
+use smol::{
+    lock::Mutex,
+    net::unix::{UnixListener, UnixStream},
+};
+
+use crate::{
+    client,
+    instance::{self, InstanceLock},
+    logging, socket,
+};
+
+/// How long `--replace` waits for the running instance to exit
+const REPLACE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often `--wait-for-hyprland` looks for a running instance
+const HYPRLAND_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Make sure there is a Hyprland instance to talk to, waiting for one to come
+/// up if `wait` is set. When our `HYPRLAND_INSTANCE_SIGNATURE` is missing or
+/// stale, as can happen under systemd, the newest running instance is used.
+pub fn find_hyprland(wait: bool) -> bool {
+    let mut waiting = false;
+
+    loop {
+        let current = env::var("HYPRLAND_INSTANCE_SIGNATURE").ok();
+
+        if let Some(his) = current.as_deref()
+            && hyprland::instance::is_running(his)
+        {
+            return true;
+        }
+
+        if let Some(his) = hyprland::instance::discover() {
+            info!("Using Hyprland instance {his}");
+
+            // safety: we're still single-threaded at this point
+            unsafe {
+                env::set_var("HYPRLAND_INSTANCE_SIGNATURE", his);
+            }
+
+            return true;
+        }
+
+        if !wait {
+            return false;
+        }
+
+        if !waiting {
+            info!("Waiting for Hyprland to start");
+            systemd::status("Waiting for Hyprland");
+            waiting = true;
+        }
+
+        thread::sleep(HYPRLAND_POLL_INTERVAL);
+    }
+}
+
+/// Send the buffered logs, then keep sending new records as they come in when
+/// following. Nothing in here may log, since that would feed itself.
+async fn send_logs(mut stream: UnixStream, follow: bool) -> io::Result<()> {
+    let (records, rx) = logging::subscribe();
+    let res = serde_json::to_string(&IpcResponse::Logs(records))?;
+    socket::send(&mut stream, res).await?;
+
+    if !follow {
+        return Ok(());
+    }
+
+    while let Ok(record) = rx.recv().await {
+        let res = serde_json::to_string(&IpcResponse::Logs(vec![record]))?;
+
+        // the client hanging up is how following ends
+        if socket::send(&mut stream, res).await.is_err() {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+async fn handle(mut stream: UnixStream, tx: &Mutex<IpcSender>) -> Result<(), Box<dyn Error>> {
+    if !socket::authorize(&stream)? {
+        warn!("Rejecting connection from another user");
+        return Ok(());
+    }
+
+    let message = socket::receive(&mut stream).await?;
+
+    let res = match IpcMessage::from_str(message.trim()) {
+        // logging lives in this process rather than the UI, and following
+        // must not hold up other connections
+        Ok(IpcMessage::Logs { follow }) => {
+            smol::spawn(send_logs(stream, follow)).detach();
+            return Ok(());
+        }
+        Ok(IpcMessage::LogLevel(directives)) => match logging::set_level(&directives) {
+            Ok(()) => IpcResponse::Ok,
+            Err(e) => IpcResponse::error(e),
+        },
+        Ok(message) => {
+            debug!("Got message: {message:?}");
+            tx.lock().await.send(message).await?
+        }
+        Err(e) => {
+            warn!("Failed to parse message {message:?}: {e}");
+            IpcResponse::error(e)
+        }
+    };
+
+    debug!("Sending response: {res:?}");
+    socket::send(&mut stream, serde_json::to_string(&res)?).await?;
+
+    Ok(())
+}
+
+fn serve(listener: UnixListener, tx: IpcSender) {
+    // the UI answers one message at a time, so only one connection may wait
+    // on it at once
+    let tx = Arc::new(Mutex::new(tx));
+
+    smol::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            debug!("Got new connection");
+            let tx = Arc::clone(&tx);
+
+            // a client that never sends anything mustn't hold up the others
+            smol::spawn(async move {
+                if let Err(e) = handle(stream, &tx).await {
+                    error!("Failed to handle connection: {e}");
+                }
+
+                debug!("Dropping connection");
+            })
+            .detach();
+        }
+    })
+    .detach();
+}
+
+pub async fn start_bar(
+    socket: PathBuf,
+    replace: bool,
+    config: Config,
+    config_path: PathBuf,
+) -> Result<(), Box<dyn Error>> {
+    let (tx, rx) = gnyprland_relay::channel::<IpcMessage, IpcResponse>();
+
+    // check if gnyprland is already running
+    let lock_path = instance::lock_path(&socket);
+    let _lock = match InstanceLock::try_acquire(&lock_path)? {
+        Some(lock) => lock,
+        None if replace => {
+            info!("Asking the running instance to quit");
+
+            if let Err(e) = client::request(&socket, IpcMessage::Quit).await {
+                return Err(format!("Failed to ask the running instance to quit: {e}").into());
+            }
+
+            let Some(lock) = InstanceLock::acquire_timeout(&lock_path, REPLACE_TIMEOUT).await?
+            else {
+                return Err("Timed out waiting for the running instance to quit".into());
+            };
+
+            lock
+        }
+        None => {
+            let message = match InstanceLock::holder(&lock_path) {
+                Some(pid) => format!("gnyprland is already running (PID: {pid})"),
+                None => String::from("gnyprland is already running"),
+            };
+
+            return Err(message.into());
+        }
+    };
+
+    let listener = socket::bind(&socket)
+        .await
+        .map_err(|e| format!("Failed to bind to socket {}: {e}", socket.display()))?;
+
+    debug!("Listening on {}", socket.display());
+    serve(listener, tx);
+
+    // returns once the UI has run its shutdown hooks, whether that was
+    // requested over IPC or through a signal
+    info!("Starting UI");
+    gnyprland_ui::start(rx, config, config_path);
+
+    socket::remove(&socket);
+    info!("Shut down");
+
+    Ok(())
+}
@@ -0,0 +1,144 @@
+use std::{
+    env, fs,
+    io::{self, ErrorKind},
+    mem,
+    os::unix::{
+        fs::{DirBuilderExt, MetadataExt, PermissionsExt},
+        net::UnixListener as StdUnixListener,
+    },
+    path::{Path, PathBuf},
+};
+
+use rustix::{net::sockopt, process};
+use smol::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::unix::{UnixListener, UnixStream},
+};
+
+/// The directory holding the socket. Lives in `$XDG_RUNTIME_DIR` when
+/// available, which is already private to the user, and falls back to a
+/// per-uid directory in `/tmp` otherwise.
+fn runtime_dir() -> PathBuf {
+    match env::var_os("XDG_RUNTIME_DIR") {
+        Some(xrd) => PathBuf::from(xrd).join("gnyprland"),
+        None => PathBuf::from(format!("/tmp/gnyprland-{}", process::getuid().as_raw())),
+    }
+}
+
+/// The default socket path, keyed by the Hyprland instance so that nested or
+/// parallel sessions each get their own bar.
+pub fn default_path() -> PathBuf {
+    let name = match env::var("HYPRLAND_INSTANCE_SIGNATURE") {
+        Ok(his) => format!("{his}.sock"),
+        Err(_) => String::from("gnyprland.sock"),
+    };
+
+    runtime_dir().join(name)
+}
+
+/// Create the socket's parent directory with `0700` permissions, refusing to
+/// use an existing directory that belongs to somebody else or that other
+/// users can write to. Sticky directories of root or our own, like `/tmp`, are
+/// fine, since nobody else can remove or replace the socket in them. Symlinks
+/// are refused, as they could point anywhere.
+pub fn prepare_dir(dir: &Path) -> io::Result<()> {
+    match fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(dir)
+    {
+        Ok(()) => {}
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+        Err(e) => return Err(e),
+    }
+
+    let meta = fs::symlink_metadata(dir)?;
+    let uid = process::getuid().as_raw();
+
+    if meta.file_type().is_symlink() {
+        return Err(io::Error::new(
+            ErrorKind::PermissionDenied,
+            format!("{} is a symlink", dir.display()),
+        ));
+    }
+
+    if meta.permissions().mode() & 0o1000 != 0 && (meta.uid() == 0 || meta.uid() == uid) {
+        return Ok(());
+    }
+
+    if meta.uid() != uid {
+        return Err(io::Error::new(
+            ErrorKind::PermissionDenied,
+            format!("{} is owned by another user", dir.display()),
+        ));
+    }
+
+    if meta.permissions().mode() & 0o022 != 0 {
+        return Err(io::Error::new(
+            ErrorKind::PermissionDenied,
+            format!("{} is writable by other users", dir.display()),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Bind the IPC socket at `path`. A leftover socket is only removed when
+/// nothing is listening on it anymore.
+pub async fn bind(path: &Path) -> io::Result<UnixListener> {
+    if let Some(dir) = path.parent() {
+        prepare_dir(dir)?;
+    }
+
+    if fs::exists(path)? {
+        if UnixStream::connect(path).await.is_ok() {
+            return Err(io::Error::new(
+                ErrorKind::AddrInUse,
+                format!("{} is in use by another instance", path.display()),
+            ));
+        }
+
+        debug!("Removing stale socket at {}", path.display());
+        fs::remove_file(path)?;
+    }
+
+    let listener = StdUnixListener::bind(path)?;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+
+    UnixListener::try_from(listener)
+}
+
+pub async fn connect(path: &Path) -> io::Result<UnixStream> {
+    UnixStream::connect(path).await
+}
+
+pub async fn receive(stream: &mut UnixStream) -> io::Result<String> {
+    let mut length_buf = [0u8; mem::size_of::<usize>()];
+    stream.read_exact(&mut length_buf).await?;
+    let length = usize::from_be_bytes(length_buf);
+
+    let mut message_buf = vec![0u8; length];
+    stream.read_exact(&mut message_buf).await?;
+
+    String::from_utf8(message_buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+pub async fn send(stream: &mut UnixStream, message: String) -> io::Result<()> {
+    stream.write_all(&message.len().to_be_bytes()).await?;
+    stream.write_all(message.as_bytes()).await?;
+    Ok(())
+}
+
+/// Check via `SO_PEERCRED` that the peer runs as the same user as us.
+pub fn authorize(stream: &UnixStream) -> io::Result<bool> {
+    let peer = sockopt::socket_peercred(stream)?;
+    Ok(peer.uid == process::getuid())
+}
+
+pub fn remove(path: &Path) {
+    if let Err(e) = fs::remove_file(path)
+        && e.kind() != ErrorKind::NotFound
+    {
+        error!("Failed to remove socket file: {e}");
+    }
+}
@@ -0,0 +1,34 @@
+use serde::Deserialize;
+
+/// How an animation speeds up and slows down
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    #[default]
+    EaseInOut,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AnimationConfig {
+    /// How long an animation takes, in milliseconds
+    #[serde(deserialize_with = "crate::de::range::<_, _, 0, 5000>")]
+    pub duration: u64,
+    pub easing: Easing,
+    /// Jump straight to the end of every animation. Animations are also
+    /// skipped when GTK has them turned off.
+    pub reduce: bool,
+}
+
+impl Default for AnimationConfig {
+    fn default() -> Self {
+        Self {
+            duration: 100,
+            easing: Easing::EaseInOut,
+            reduce: false,
+        }
+    }
+}
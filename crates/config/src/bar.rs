@@ -0,0 +1,124 @@
+use serde::{Deserialize, Serialize};
+use toml::Table;
+
+/// The screen edge the bar sits on
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BarEdge {
+    #[default]
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+impl BarEdge {
+    /// Side bars lay their modules out top to bottom
+    pub fn is_vertical(self) -> bool {
+        matches!(self, BarEdge::Left | BarEdge::Right)
+    }
+
+    pub fn opposite(self) -> Self {
+        match self {
+            BarEdge::Top => BarEdge::Bottom,
+            BarEdge::Bottom => BarEdge::Top,
+            BarEdge::Left => BarEdge::Right,
+            BarEdge::Right => BarEdge::Left,
+        }
+    }
+}
+
+/// The layer-shell layer the bar is drawn on, from the bottom up
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BarLayer {
+    Background,
+    #[default]
+    Bottom,
+    Top,
+    Overlay,
+}
+
+/// What the bar does while the workspace it shows has a fullscreen window
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FullscreenMode {
+    /// Stay as is
+    #[default]
+    Keep,
+    /// Hide the bar altogether
+    Hide,
+    /// Stay, but stop reserving space
+    Overlap,
+}
+
+/// Space in pixels between the bar and the edges of the screen
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Margins {
+    #[serde(deserialize_with = "crate::de::range::<_, _, 0, 1024>")]
+    pub top: i32,
+    #[serde(deserialize_with = "crate::de::range::<_, _, 0, 1024>")]
+    pub right: i32,
+    #[serde(deserialize_with = "crate::de::range::<_, _, 0, 1024>")]
+    pub bottom: i32,
+    #[serde(deserialize_with = "crate::de::range::<_, _, 0, 1024>")]
+    pub left: i32,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BarConfig {
+    pub edge: BarEdge,
+
+    /// Height of the bar in pixels, or its width when it sits on the left or
+    /// right edge
+    #[serde(
+        alias = "height",
+        deserialize_with = "crate::de::range::<_, _, 1, 1024>"
+    )]
+    pub size: i32,
+
+    pub margin: Margins,
+    pub layer: BarLayer,
+
+    /// Whether windows are kept out of the space the bar takes up
+    pub exclusive: bool,
+
+    pub fullscreen: FullscreenMode,
+
+    /// Shrink the bar to a sliver along its edge until the pointer touches it.
+    /// Space is never reserved for a bar that hides this way.
+    pub autohide: bool,
+}
+
+impl Default for BarConfig {
+    fn default() -> Self {
+        Self {
+            edge: BarEdge::Top,
+            size: 57,
+            margin: Margins::default(),
+            layer: BarLayer::Bottom,
+            exclusive: true,
+            fullscreen: FullscreenMode::Keep,
+            autohide: false,
+        }
+    }
+}
+
+impl BarConfig {
+    /// Change a single setting, given as it would be written in the config.
+    /// Bare words are taken as strings, so `edge left` works as well as
+    /// `edge "left"`, and nested keys like `margin.top` are fine too.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), toml::de::Error> {
+        let overrides: Table = toml::from_str(&format!("{key} = {value}"))
+            .or_else(|_| toml::from_str(&format!("{key} = {}", toml::Value::from(value))))?;
+
+        // serializing plain data into a table can't fail
+        let mut table = Table::try_from(&*self).unwrap();
+        crate::merge(&mut table, &overrides);
+
+        *self = table.try_into()?;
+        Ok(())
+    }
+}
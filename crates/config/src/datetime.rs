@@ -0,0 +1,30 @@
+use chrono::format::{Item, StrftimeItems};
+use serde::{Deserialize, Deserializer, de::Error};
+
+fn format<'de, D: Deserializer<'de>>(de: D) -> Result<String, D::Error> {
+    let format = String::deserialize(de)?;
+
+    if StrftimeItems::new(&format).any(|item| item == Item::Error) {
+        return Err(D::Error::custom(format!(
+            "invalid strftime format string: {format:?}"
+        )));
+    }
+
+    Ok(format)
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DateTimeConfig {
+    /// A `strftime` format string for the clock
+    #[serde(deserialize_with = "format")]
+    pub format: String,
+}
+
+impl Default for DateTimeConfig {
+    fn default() -> Self {
+        Self {
+            format: String::from("%A, %b %d  %l:%M %p"),
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Deserializer, de::Error};
+
+/// Deserialize an integer and check that it falls within `MIN..=MAX`. Failing
+/// here instead of after loading lets toml report where the value is.
+pub fn range<'de, D, T, const MIN: i64, const MAX: i64>(de: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: TryFrom<i64>,
+    T::Error: Display,
+{
+    let value = i64::deserialize(de)?;
+
+    if !(MIN..=MAX).contains(&value) {
+        return Err(D::Error::custom(format!(
+            "expected a value between {MIN} and {MAX}, found {value}"
+        )));
+    }
+
+    T::try_from(value).map_err(D::Error::custom)
+}
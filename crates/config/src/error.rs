@@ -0,0 +1,29 @@
+use std::{io, path::PathBuf};
+
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Failed to read {}: {source}", path.display())]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    // toml errors already point at the offending line and column
+    #[error("Invalid config in {}:\n{source}", path.display())]
+    Parse {
+        path: PathBuf,
+        #[source]
+        source: toml::de::Error,
+    },
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum DirectivesError {
+    #[error("Invalid log level {0:?}, expected off, error, warn, info, debug or trace")]
+    InvalidLevel(String),
+    #[error("Missing a module name in {0:?}")]
+    MissingModule(String),
+}
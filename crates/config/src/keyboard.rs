@@ -0,0 +1,33 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeyboardConfig {
+    /// The keyboard to show and switch the layout of, by name as in
+    /// `hyprctl devices`. Empty for the main keyboard.
+    pub device: String,
+    /// Short codes for layouts, keyed by their full name. Layouts without
+    /// one show the first two letters of their name.
+    pub labels: HashMap<String, String>,
+}
+
+impl Default for KeyboardConfig {
+    fn default() -> Self {
+        Self {
+            device: String::new(),
+            labels: HashMap::from([(String::from("English (US)"), String::from("EN"))]),
+        }
+    }
+}
+
+impl KeyboardConfig {
+    /// The short code for the layout called `layout`
+    pub fn label(&self, layout: &str) -> String {
+        match self.labels.get(layout) {
+            Some(label) => label.clone(),
+            None => layout.chars().take(2).collect::<String>().to_uppercase(),
+        }
+    }
+}
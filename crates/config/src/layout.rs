@@ -0,0 +1,45 @@
+use serde::Deserialize;
+
+/// The modules the bar knows how to build, named as in the config
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ModuleKind {
+    /// Title and class of the active window, configured by `[window]`
+    Window,
+    /// Workspace slider and indicator, configured by `[workspaces]`
+    Workspaces,
+    /// The clock, configured by `[datetime]`
+    Datetime,
+    /// Open windows to focus, configured by `[taskbar]`
+    Taskbar,
+    /// The active submap, hidden in the default one, configured by `[submap]`
+    Submap,
+    /// The keyboard layout, switched on click, configured by `[keyboard]`
+    Keyboard,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LayoutConfig {
+    /// Modules at the start of the bar, in order
+    pub start: Vec<ModuleKind>,
+    /// Modules in the center of the bar, in order
+    pub center: Vec<ModuleKind>,
+    /// Modules at the end of the bar, in order
+    pub end: Vec<ModuleKind>,
+
+    /// Space in pixels between the modules of a slot
+    #[serde(deserialize_with = "crate::de::range::<_, _, 0, 256>")]
+    pub spacing: i32,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        Self {
+            start: vec![ModuleKind::Window, ModuleKind::Workspaces],
+            center: vec![ModuleKind::Datetime],
+            end: vec![],
+            spacing: 8,
+        }
+    }
+}
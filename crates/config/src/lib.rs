@@ -0,0 +1,164 @@
+extern crate chrono;
+extern crate log;
+extern crate regex;
+extern crate serde;
+extern crate thiserror;
+extern crate toml;
+
+mod animation;
+mod bar;
+mod datetime;
+mod de;
+pub mod error;
+mod keyboard;
+mod layout;
+mod logging;
+mod rules;
+mod submap;
+mod taskbar;
+mod window;
+mod workspaces;
+
+use std::{
+    collections::HashMap,
+    env, fmt, fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+pub use animation::{AnimationConfig, Easing};
+pub use bar::{BarConfig, BarEdge, BarLayer, FullscreenMode, Margins};
+pub use datetime::DateTimeConfig;
+use error::ConfigError;
+pub use keyboard::KeyboardConfig;
+pub use layout::{LayoutConfig, ModuleKind};
+pub use logging::{LogConfig, LogDirectives};
+pub use rules::{Matched, Pattern, PatternKind, Rule};
+use serde::{Deserialize, de::Error};
+pub use submap::SubmapConfig;
+pub use taskbar::{TaskbarConfig, TaskbarFilter};
+use toml::Table;
+pub use window::WindowConfig;
+pub use workspaces::{WorkspacesConfig, WorkspacesMode};
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub animation: AnimationConfig,
+    pub bar: BarConfig,
+    pub datetime: DateTimeConfig,
+    pub keyboard: KeyboardConfig,
+    pub layout: LayoutConfig,
+    pub log: LogConfig,
+    pub submap: SubmapConfig,
+    pub taskbar: TaskbarConfig,
+    pub window: WindowConfig,
+    pub workspaces: WorkspacesConfig,
+
+    /// Sections overriding the ones above on a single monitor, keyed by
+    /// connector name
+    monitors: HashMap<String, Table>,
+
+    /// The full config of every monitor with overrides
+    #[serde(skip)]
+    resolved: HashMap<String, Config>,
+}
+
+/// Overlay `overrides` onto `base`, merging tables key by key
+pub(crate) fn merge(base: &mut Table, overrides: &Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(value)) => merge(base, value),
+            _ => {
+                base.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+impl Config {
+    /// `$XDG_CONFIG_HOME/gnyprland/config.toml`, falling back to `~/.config`
+    pub fn default_path() -> PathBuf {
+        let dir = match env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(env::var_os("HOME").unwrap_or_default()).join(".config"),
+        };
+
+        dir.join("gnyprland").join("config.toml")
+    }
+
+    pub fn parse(source: &str) -> Result<Self, toml::de::Error> {
+        // parse straight from the source first, so that errors carry a location
+        let mut config: Self = toml::from_str(source)?;
+
+        let mut base: Table = toml::from_str(source)?;
+        base.remove("monitors");
+
+        for (monitor, overrides) in &config.monitors {
+            let invalid = |e: &dyn fmt::Display| {
+                toml::de::Error::custom(format!("in [monitors.{monitor:?}]: {e}"))
+            };
+
+            if let Some(key) = ["log", "monitors"]
+                .iter()
+                .find(|k| overrides.contains_key(**k))
+            {
+                return Err(invalid(&format!("`{key}` can't be set per monitor")));
+            }
+
+            let mut table = base.clone();
+            merge(&mut table, overrides);
+
+            let resolved = table.try_into().map_err(|e| invalid(&e))?;
+            config.resolved.insert(monitor.clone(), resolved);
+        }
+
+        Ok(config)
+    }
+
+    /// Change a setting of the `[bar]` section everywhere, see
+    /// [`BarConfig::set`]. Nothing changes if the result would be invalid.
+    pub fn set_bar(&mut self, key: &str, value: &str) -> Result<(), toml::de::Error> {
+        let mut bar = self.bar.clone();
+        bar.set(key, value)?;
+
+        let mut resolved = self.resolved.clone();
+
+        for config in resolved.values_mut() {
+            config.bar.set(key, value)?;
+        }
+
+        self.bar = bar;
+        self.resolved = resolved;
+
+        Ok(())
+    }
+
+    /// The config of the monitor with connector `name`, with its overrides
+    pub fn for_monitor(&self, name: &str) -> Config {
+        self.resolved.get(name).unwrap_or(self).clone()
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let source = fs::read_to_string(path).map_err(|source| ConfigError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        Self::parse(&source).map_err(|source| ConfigError::Parse {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// Like [`Config::load`], but a missing file means the defaults. Used for
+    /// the default path, where having no config at all is fine.
+    pub fn load_or_default(path: &Path) -> Result<Self, ConfigError> {
+        match Self::load(path) {
+            Err(ConfigError::Read { source, .. }) if source.kind() == ErrorKind::NotFound => {
+                Ok(Self::default())
+            }
+            res => res,
+        }
+    }
+}
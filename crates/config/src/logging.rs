@@ -0,0 +1,130 @@
+use std::{fmt, path::PathBuf, str::FromStr};
+
+use log::LevelFilter;
+use serde::Deserialize;
+
+use crate::error::DirectivesError;
+
+/// Log levels in the form `info,gnyprland_ui::bar=debug`. A bare level sets
+/// the default, `module=level` overrides it for a module and everything below
+/// it.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct LogDirectives {
+    pub default: Option<LevelFilter>,
+    pub modules: Vec<(String, LevelFilter)>,
+}
+
+impl LogDirectives {
+    /// Apply `other` on top of these directives
+    pub fn merge(&mut self, other: LogDirectives) {
+        if other.default.is_some() {
+            self.default = other.default;
+        }
+
+        for (module, level) in other.modules {
+            self.modules.retain(|(m, _)| *m != module);
+            self.modules.push((module, level));
+        }
+    }
+
+    /// The level set for `target` by its most specific module, if any
+    pub fn level(&self, target: &str) -> Option<LevelFilter> {
+        self.modules
+            .iter()
+            .filter(|(module, _)| {
+                target
+                    .strip_prefix(module.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .max_by_key(|(module, _)| module.len())
+            .map(|(_, level)| *level)
+            .or(self.default)
+    }
+
+    /// The most verbose level any of the directives allows
+    pub fn max(&self) -> Option<LevelFilter> {
+        self.modules
+            .iter()
+            .map(|(_, level)| *level)
+            .chain(self.default)
+            .max()
+    }
+}
+
+impl FromStr for LogDirectives {
+    type Err = DirectivesError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut directives = LogDirectives::default();
+
+        for directive in s.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            let parse_level = |level: &str| {
+                LevelFilter::from_str(level)
+                    .map_err(|_| DirectivesError::InvalidLevel(level.to_string()))
+            };
+
+            match directive.split_once('=') {
+                Some((module, _)) if module.trim().is_empty() => {
+                    return Err(DirectivesError::MissingModule(directive.to_string()));
+                }
+                Some((module, level)) => directives
+                    .modules
+                    .push((module.trim().to_string(), parse_level(level.trim())?)),
+                None => directives.default = Some(parse_level(directive)?),
+            }
+        }
+
+        Ok(directives)
+    }
+}
+
+impl TryFrom<String> for LogDirectives {
+    type Error = DirectivesError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for LogDirectives {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let default = self.default.map(|level| level.to_string().to_lowercase());
+        let modules = self
+            .modules
+            .iter()
+            .map(|(module, level)| format!("{module}={}", level.to_string().to_lowercase()));
+
+        let directives = default.into_iter().chain(modules).collect::<Vec<_>>();
+        write!(f, "{}", directives.join(","))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    /// Applied on top of the build's default level when the bar starts
+    pub level: LogDirectives,
+
+    /// Also write logs to this file
+    pub file: Option<PathBuf>,
+
+    /// Size in bytes the log file may grow to before it is rotated
+    #[serde(deserialize_with = "crate::de::range::<_, _, 1024, 1073741824>")]
+    pub max_size: u64,
+
+    /// How many rotated files to keep around
+    #[serde(deserialize_with = "crate::de::range::<_, _, 0, 16>")]
+    pub keep: usize,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            level: LogDirectives::default(),
+            file: None,
+            max_size: 1024 * 1024,
+            keep: 3,
+        }
+    }
+}
@@ -0,0 +1,367 @@
+use regex::{Captures, Regex};
+use serde::Deserialize;
+
+/// How a pattern was written in the config
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum PatternKind {
+    Exact(String),
+    /// `*` matches any run of characters and `?` a single one, each being a
+    /// capture group in order
+    Glob(String),
+    Regex(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+enum PatternSource {
+    /// A plain string is matched exactly
+    Plain(String),
+    Kind(PatternKind),
+}
+
+/// Matches a whole value, with capture groups for templates. Every kind of
+/// pattern is turned into an anchored regex.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(try_from = "PatternSource")]
+pub struct Pattern {
+    pub kind: PatternKind,
+    regex: Regex,
+}
+
+impl Pattern {
+    pub fn new(kind: PatternKind) -> Result<Self, regex::Error> {
+        let source = match &kind {
+            PatternKind::Exact(value) => regex::escape(value),
+            PatternKind::Glob(glob) => glob
+                .chars()
+                .map(|c| match c {
+                    '*' => String::from("(.*)"),
+                    '?' => String::from("(.)"),
+                    c => regex::escape(c.encode_utf8(&mut [0; 4])),
+                })
+                .collect(),
+            PatternKind::Regex(regex) => {
+                // check it alone first, so errors point into what was written
+                Regex::new(regex)?;
+                regex.clone()
+            }
+        };
+
+        let regex = Regex::new(&format!("^(?:{source})$"))?;
+        Ok(Self { kind, regex })
+    }
+
+    pub fn captures<'a>(&self, value: &'a str) -> Option<Captures<'a>> {
+        self.regex.captures(value)
+    }
+}
+
+impl TryFrom<PatternSource> for Pattern {
+    type Error = regex::Error;
+
+    fn try_from(source: PatternSource) -> Result<Self, Self::Error> {
+        match source {
+            PatternSource::Plain(value) => Self::new(PatternKind::Exact(value)),
+            PatternSource::Kind(kind) => Self::new(kind),
+        }
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+    }
+}
+
+/// Rewrites the class and/or title of windows that match. Leaving out both
+/// `class` and `title` matches every window.
+///
+/// Templates take `$1` or `${name}` for capture groups of the title pattern,
+/// then the class pattern, and `$title` or `$class` for the whole values. `$$`
+/// is a literal `$`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    #[serde(default)]
+    pub class: Option<Pattern>,
+    #[serde(default)]
+    pub title: Option<Pattern>,
+
+    #[serde(default)]
+    pub rewrite_title: Option<String>,
+    #[serde(default)]
+    pub rewrite_class: Option<String>,
+}
+
+/// The values a rule matched, with the captures of its patterns
+pub struct Matched<'a> {
+    pub class: &'a str,
+    pub title: &'a str,
+    class_captures: Option<Captures<'a>>,
+    title_captures: Option<Captures<'a>>,
+}
+
+impl Rule {
+    pub fn matches<'a>(&self, class: &'a str, title: &'a str) -> Option<Matched<'a>> {
+        let class_captures = match &self.class {
+            Some(pattern) => Some(pattern.captures(class)?),
+            None => None,
+        };
+
+        let title_captures = match &self.title {
+            Some(pattern) => Some(pattern.captures(title)?),
+            None => None,
+        };
+
+        Some(Matched {
+            class,
+            title,
+            class_captures,
+            title_captures,
+        })
+    }
+}
+
+/// The group `name` of `captures`, by index or name
+fn group<'a>(captures: Option<&Captures<'a>>, name: &str) -> Option<&'a str> {
+    let captures = captures?;
+    let group = match name.parse::<usize>() {
+        Ok(index) => captures.get(index),
+        Err(_) => captures.name(name),
+    };
+
+    group.map(|group| group.as_str())
+}
+
+impl<'a> Matched<'a> {
+    fn lookup(&self, name: &str) -> Option<&'a str> {
+        group(self.title_captures.as_ref(), name)
+            .or_else(|| group(self.class_captures.as_ref(), name))
+            .or(match name {
+                "title" => Some(self.title),
+                "class" => Some(self.class),
+                _ => None,
+            })
+    }
+
+    /// Fill in `template`, leaving unknown groups empty
+    pub fn expand(&self, template: &str) -> String {
+        let mut out = String::with_capacity(template.len());
+        let mut rest = template;
+
+        while let Some(start) = rest.find('$') {
+            out.push_str(&rest[..start]);
+            rest = &rest[start + 1..];
+
+            if let Some(after) = rest.strip_prefix('$') {
+                out.push('$');
+                rest = after;
+                continue;
+            }
+
+            let (name, after) = match rest.strip_prefix('{') {
+                Some(braced) => match braced.find('}') {
+                    Some(end) => (&braced[..end], &braced[end + 1..]),
+                    None => ("", rest),
+                },
+                None => {
+                    let end = rest
+                        .find(|c: char| !c.is_alphanumeric() && c != '_')
+                        .unwrap_or(rest.len());
+
+                    (&rest[..end], &rest[end..])
+                }
+            };
+
+            // a lone `$` stays as it is
+            if name.is_empty() {
+                out.push('$');
+                continue;
+            }
+
+            out.push_str(self.lookup(name).unwrap_or_default());
+            rest = after;
+        }
+
+        out.push_str(rest);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(source: &str) -> Rule {
+        toml::from_str(source).unwrap()
+    }
+
+    fn rewrite_title(rule: &Rule, class: &str, title: &str) -> Option<String> {
+        let template = rule.rewrite_title.as_deref()?;
+        Some(rule.matches(class, title)?.expand(template))
+    }
+
+    #[test]
+    fn exact_matches_whole_value() {
+        let rule = rule(r#"class = "kitty""#);
+
+        assert!(rule.matches("kitty", "").is_some());
+        assert!(rule.matches("kitty2", "").is_none());
+        assert!(rule.matches("Kitty", "").is_none());
+    }
+
+    #[test]
+    fn exact_escapes_regex_syntax() {
+        let rule = rule(r#"class = "a.b""#);
+
+        assert!(rule.matches("a.b", "").is_some());
+        assert!(rule.matches("axb", "").is_none());
+    }
+
+    #[test]
+    fn glob_captures_wildcards() {
+        let rule = rule(
+            r#"
+            title = { glob = "* - Visual Studio Code" }
+            rewrite_title = "$1"
+            "#,
+        );
+
+        assert_eq!(
+            rewrite_title(&rule, "code", "main.rs - Visual Studio Code").as_deref(),
+            Some("main.rs")
+        );
+        assert_eq!(rewrite_title(&rule, "code", "Visual Studio Code"), None);
+    }
+
+    #[test]
+    fn glob_question_mark_is_one_character() {
+        let rule = rule(r#"class = { glob = "steam_app_?" }"#);
+
+        assert!(rule.matches("steam_app_1", "").is_some());
+        assert!(rule.matches("steam_app_12", "").is_none());
+    }
+
+    #[test]
+    fn regex_is_anchored() {
+        let rule = rule(r#"class = { regex = "fire.*" }"#);
+
+        assert!(rule.matches("firefox", "").is_some());
+        assert!(rule.matches("not-firefox", "").is_none());
+    }
+
+    #[test]
+    fn regex_alternation_is_anchored_as_a_whole() {
+        let rule = rule(r#"class = { regex = "a|b" }"#);
+
+        assert!(rule.matches("a", "").is_some());
+        assert!(rule.matches("ab", "").is_none());
+    }
+
+    #[test]
+    fn class_and_title_must_both_match() {
+        let rule = rule(
+            r#"
+            class = "firefox"
+            title = { glob = "*Private*" }
+            "#,
+        );
+
+        assert!(rule.matches("firefox", "Private Browsing").is_some());
+        assert!(rule.matches("firefox", "Inbox").is_none());
+        assert!(rule.matches("chromium", "Private Browsing").is_none());
+    }
+
+    #[test]
+    fn no_patterns_match_everything() {
+        assert!(rule("").matches("anything", "at all").is_some());
+    }
+
+    #[test]
+    fn named_captures() {
+        let rule = rule(
+            r#"
+            title = { regex = "(?<page>.*) — Mozilla Firefox" }
+            rewrite_title = "${page} (Firefox)"
+            "#,
+        );
+
+        assert_eq!(
+            rewrite_title(&rule, "firefox", "Docs — Mozilla Firefox").as_deref(),
+            Some("Docs (Firefox)")
+        );
+    }
+
+    #[test]
+    fn title_captures_come_before_class_captures() {
+        let rule = rule(
+            r#"
+            class = { regex = "(class)" }
+            title = { regex = "(title)" }
+            rewrite_title = "$1"
+            "#,
+        );
+
+        assert_eq!(
+            rewrite_title(&rule, "class", "title").as_deref(),
+            Some("title")
+        );
+    }
+
+    #[test]
+    fn class_captures_fill_in_for_missing_title_groups() {
+        let rule = rule(
+            r#"
+            class = { regex = "org\\.(?<app>\\w+)" }
+            rewrite_title = "${app}: $title"
+            "#,
+        );
+
+        assert_eq!(
+            rewrite_title(&rule, "org.gimp", "Untitled").as_deref(),
+            Some("gimp: Untitled")
+        );
+    }
+
+    #[test]
+    fn whole_values_in_templates() {
+        let rule = rule(r#"rewrite_title = "$class | $title""#);
+
+        assert_eq!(rewrite_title(&rule, "a", "b").as_deref(), Some("a | b"));
+    }
+
+    #[test]
+    fn template_escapes_and_unknown_groups() {
+        let rule = rule(
+            r#"
+            title = { regex = "(.*)" }
+            rewrite_title = "$$1 costs $ ${missing}$9"
+            "#,
+        );
+
+        assert_eq!(
+            rewrite_title(&rule, "", "x").as_deref(),
+            Some("$1 costs $ ")
+        );
+    }
+
+    #[test]
+    fn unterminated_brace_is_literal() {
+        let rule = rule(r#"rewrite_title = "${oops""#);
+
+        assert_eq!(rewrite_title(&rule, "", "").as_deref(), Some("${oops"));
+    }
+
+    #[test]
+    fn invalid_regex_is_a_parse_error() {
+        let err = toml::from_str::<Rule>(r#"class = { regex = "(" }"#).unwrap_err();
+
+        assert!(err.message().contains("regex"), "{err}");
+    }
+
+    #[test]
+    fn unknown_pattern_kind_is_a_parse_error() {
+        assert!(toml::from_str::<Rule>(r#"class = { wildcard = "*" }"#).is_err());
+    }
+}
@@ -0,0 +1,17 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SubmapConfig {
+    /// What to show for each submap, keyed by name. Submaps without a label
+    /// show their name.
+    pub labels: HashMap<String, String>,
+}
+
+impl SubmapConfig {
+    pub fn label<'a>(&'a self, submap: &'a str) -> &'a str {
+        self.labels.get(submap).map_or(submap, String::as_str)
+    }
+}
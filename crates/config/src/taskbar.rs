@@ -0,0 +1,39 @@
+use serde::Deserialize;
+
+/// Which windows the taskbar lists
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TaskbarFilter {
+    /// Every window
+    All,
+    /// Windows on any workspace of the bar's monitor
+    Monitor,
+    /// Windows on the workspace shown on the bar's monitor
+    #[default]
+    Workspace,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TaskbarConfig {
+    pub filter: TaskbarFilter,
+    /// Show titles, as rewritten by the `[window]` rules, next to the icons
+    pub titles: bool,
+    /// How many characters of a title to show before cutting it off
+    #[serde(deserialize_with = "crate::de::range::<_, _, 1, 100>")]
+    pub title_width: i32,
+    /// Size of the icons in pixels
+    #[serde(deserialize_with = "crate::de::range::<_, _, 8, 64>")]
+    pub icon_size: i32,
+}
+
+impl Default for TaskbarConfig {
+    fn default() -> Self {
+        Self {
+            filter: TaskbarFilter::Workspace,
+            titles: true,
+            title_width: 16,
+            icon_size: 20,
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use serde::Deserialize;
+
+use crate::rules::Rule;
+
+/// The rules that come without a config, the same as in `contrib/config.toml`
+const DEFAULT_RULES: &str = r#"
+[[rules]]
+class = ""
+rewrite_title = "Desktop"
+rewrite_class = "desktop"
+
+[[rules]]
+class = "kitty"
+rewrite_title = "Terminal"
+
+[[rules]]
+class = "code"
+title = { glob = "* - Visual Studio Code" }
+rewrite_title = "$1"
+
+# Only the last "Mozilla Firefox" is shortened, which is the browser's own
+# suffix rather than part of the page title
+[[rules]]
+class = "firefox"
+title = { regex = "(?<page>.*)Mozilla Firefox(?<rest>.*)" }
+rewrite_title = "${page}Firefox${rest}"
+
+[[rules]]
+class = "dev.zed.Zed"
+rewrite_class = "zed"
+"#;
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WindowConfig {
+    /// Tried in order. The title and class are each rewritten by the first
+    /// matching rule that rewrites them.
+    pub rules: Vec<Rule>,
+    /// Show the name from an app's desktop entry when no rule rewrites its
+    /// class
+    pub desktop_names: bool,
+    /// Show the app's icon from its desktop entry next to the title
+    pub icon: bool,
+    /// Size of the icon in pixels
+    #[serde(deserialize_with = "crate::de::range::<_, _, 8, 128>")]
+    pub icon_size: i32,
+    /// Show the windows of the active window's group as tabs to switch
+    /// between
+    pub group_tabs: bool,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        #[derive(Deserialize)]
+        struct Rules {
+            rules: Vec<Rule>,
+        }
+
+        let Rules { rules } = toml::from_str(DEFAULT_RULES).unwrap();
+
+        Self {
+            rules,
+            desktop_names: true,
+            icon: true,
+            icon_size: 24,
+            group_tabs: true,
+        }
+    }
+}
+
+impl WindowConfig {
+    pub fn title(&self, class: &str, title: String) -> String {
+        self.rules
+            .iter()
+            .find_map(|rule| {
+                let template = rule.rewrite_title.as_deref()?;
+                Some(rule.matches(class, &title)?.expand(template))
+            })
+            .unwrap_or(title)
+    }
+
+    /// The rewritten class, if any rule rewrites it
+    pub fn class(&self, class: &str, title: &str) -> Option<String> {
+        self.rules.iter().find_map(|rule| {
+            let template = rule.rewrite_class.as_deref()?;
+            Some(rule.matches(class, title)?.expand(template))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults() {
+        let config = WindowConfig::default();
+
+        assert_eq!(config.title("kitty", String::from("~/src")), "Terminal");
+        assert_eq!(
+            config.title(
+                "code",
+                String::from("lib.rs - gnyprland - Visual Studio Code")
+            ),
+            "lib.rs - gnyprland"
+        );
+        assert_eq!(
+            config.title("firefox", String::from("Docs — Mozilla Firefox")),
+            "Docs — Firefox"
+        );
+        assert_eq!(
+            config.title(
+                "firefox",
+                String::from("Docs — Mozilla Firefox Private Browsing")
+            ),
+            "Docs — Firefox Private Browsing"
+        );
+        assert_eq!(
+            config.title(
+                "firefox",
+                String::from("Mozilla Firefox Support — Mozilla Firefox")
+            ),
+            "Mozilla Firefox Support — Firefox"
+        );
+        assert_eq!(config.title("", String::new()), "Desktop");
+        assert_eq!(config.class("", "").as_deref(), Some("desktop"));
+        assert_eq!(
+            config.class("dev.zed.Zed", "main.rs").as_deref(),
+            Some("zed")
+        );
+    }
+
+    #[test]
+    fn unmatched_values_are_kept() {
+        let config = WindowConfig::default();
+
+        assert_eq!(config.title("foot", String::from("htop")), "htop");
+        assert_eq!(config.class("foot", "htop"), None);
+    }
+
+    #[test]
+    fn first_rewriting_rule_wins() {
+        let config: WindowConfig = toml::from_str(
+            r#"
+            [[rules]]
+            class = "foot"
+            rewrite_class = "Foot"
+
+            [[rules]]
+            class = "foot"
+            rewrite_title = "first"
+
+            [[rules]]
+            class = { glob = "*" }
+            rewrite_title = "second"
+            rewrite_class = "second"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.title("foot", String::new()), "first");
+        assert_eq!(config.class("foot", "").as_deref(), Some("Foot"));
+        assert_eq!(config.class("kitty", "").as_deref(), Some("second"));
+    }
+
+    #[test]
+    fn rules_replace_the_defaults() {
+        let config: WindowConfig = toml::from_str("rules = []").unwrap();
+
+        assert_eq!(config.title("kitty", String::from("~")), "~");
+        assert!(config.desktop_names);
+    }
+}
@@ -0,0 +1,49 @@
+use serde::Deserialize;
+
+/// Which workspaces the indicator and slider have a place for
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WorkspacesMode {
+    /// Workspaces 1 to `count`, and any others that exist
+    #[default]
+    Static,
+    /// Only the workspaces that exist
+    Existing,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkspacesConfig {
+    pub mode: WorkspacesMode,
+    /// How many workspaces the indicator and slider always show in static
+    /// mode
+    #[serde(deserialize_with = "crate::de::range::<_, _, 1, 64>")]
+    pub count: usize,
+    /// Show special workspaces, like the scratchpad, next to the others
+    pub special: bool,
+    /// Show the icons of the windows on each workspace in place of the dots
+    /// and slider, with their titles on hover
+    pub icons: bool,
+    /// Size of the icons in pixels
+    #[serde(deserialize_with = "crate::de::range::<_, _, 8, 64>")]
+    pub icon_size: i32,
+
+    /// Scroll from the last workspace around to the first and back
+    pub wrap: bool,
+    /// Scroll past workspaces without windows
+    pub skip_empty: bool,
+}
+
+impl Default for WorkspacesConfig {
+    fn default() -> Self {
+        Self {
+            mode: WorkspacesMode::Static,
+            count: 10,
+            special: true,
+            icons: false,
+            icon_size: 16,
+            wrap: false,
+            skip_empty: false,
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use std::env;
+use std::fmt;
 
 use serde::Deserialize;
 use smol::{
@@ -6,7 +6,7 @@ use smol::{
     net::unix::UnixStream,
 };
 
-use crate::error::CommandError;
+use crate::{error::CommandError, instance};
 
 pub trait Command: Sized + Send + Sync + 'static {
     type Response: for<'de> Deserialize<'de> + Send + Sync;
@@ -14,26 +14,90 @@ pub trait Command: Sized + Send + Sync + 'static {
     const NAME: &'static str;
 }
 
-pub enum Executor {}
+/// A dispatcher along with its arguments, as `hyprctl dispatch` takes them
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Dispatch {
+    /// Close the window with the given address
+    CloseWindow(String),
+    ToggleFloating(String),
+    Pin(String),
+    FocusWindow(String),
+    /// Fullscreen the active window
+    Fullscreen,
+    /// Move a window without following it. `workspace` is anything Hyprland
+    /// takes as a workspace, like `3` or `special:scratch`.
+    MoveToWorkspaceSilent {
+        workspace: String,
+        window: String,
+    },
+    /// Focus the next or previous window on the active workspace
+    CycleNext {
+        prev: bool,
+    },
+    /// Focus the monitor with the given connector name
+    FocusMonitor(String),
+    /// Switch to a workspace, given as anything Hyprland takes, like `3` or
+    /// `name:web`
+    Workspace(String),
+    /// Switch the focused window's group to the member at this index,
+    /// counting from 0
+    ChangeGroupActive(usize),
+    /// Switch a keyboard to its next or previous layout. `device` is a
+    /// keyboard name, `current` for the main keyboard or `all`.
+    SwitchXkbLayout {
+        device: String,
+        prev: bool,
+    },
+}
 
-impl Executor {
-    fn socket() -> Result<String, env::VarError> {
-        let his = env::var("HYPRLAND_INSTANCE_SIGNATURE")?;
-        let xrd = env::var("XDG_RUNTIME_DIR")?;
-        Ok(format!("{xrd}/hypr/{his}/.socket.sock"))
+impl fmt::Display for Dispatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Dispatch::CloseWindow(window) => write!(f, "closewindow address:{window}"),
+            Dispatch::ToggleFloating(window) => write!(f, "togglefloating address:{window}"),
+            Dispatch::Pin(window) => write!(f, "pin address:{window}"),
+            Dispatch::FocusWindow(window) => write!(f, "focuswindow address:{window}"),
+            Dispatch::Fullscreen => write!(f, "fullscreen 0"),
+            Dispatch::MoveToWorkspaceSilent { workspace, window } => {
+                write!(f, "movetoworkspacesilent {workspace},address:{window}")
+            }
+            Dispatch::CycleNext { prev: false } => write!(f, "cyclenext"),
+            Dispatch::CycleNext { prev: true } => write!(f, "cyclenext prev"),
+            Dispatch::FocusMonitor(monitor) => write!(f, "focusmonitor {monitor}"),
+            Dispatch::Workspace(workspace) => write!(f, "workspace {workspace}"),
+            // Hyprland counts group members from 1
+            Dispatch::ChangeGroupActive(index) => write!(f, "changegroupactive {}", index + 1),
+            Dispatch::SwitchXkbLayout {
+                device,
+                prev: false,
+            } => {
+                write!(f, "switchxkblayout {device} next")
+            }
+            Dispatch::SwitchXkbLayout { device, prev: true } => {
+                write!(f, "switchxkblayout {device} prev")
+            }
+        }
     }
+}
 
-    pub async fn command_async<C: Command>() -> Result<C::Response, CommandError> {
-        let socket = Self::socket()?;
+pub enum Executor {}
+
+impl Executor {
+    /// Send `request` to the command socket and read the whole reply
+    async fn request(request: &str) -> Result<String, CommandError> {
+        let socket = instance::socket(".socket.sock")?;
         let mut stream = UnixStream::connect(socket).await?;
 
-        stream
-            .write_all(format!("-j/{}", C::NAME).as_bytes())
-            .await?;
+        stream.write_all(request.as_bytes()).await?;
 
         let mut buf = String::new();
         stream.read_to_string(&mut buf).await?;
 
+        Ok(buf)
+    }
+
+    pub async fn command_async<C: Command>() -> Result<C::Response, CommandError> {
+        let buf = Self::request(&format!("-j/{}", C::NAME)).await?;
         let res = serde_json::from_str(&buf)?;
 
         Ok(res)
@@ -42,6 +106,19 @@ impl Executor {
     pub fn command<C: Command>() -> Result<C::Response, CommandError> {
         smol::block_on(Self::command_async::<C>())
     }
+
+    pub async fn dispatch_async(dispatch: &Dispatch) -> Result<(), CommandError> {
+        let reply = Self::request(&format!("dispatch {dispatch}")).await?;
+
+        match reply.trim() {
+            "ok" => Ok(()),
+            reply => Err(CommandError::Dispatch(reply.to_string())),
+        }
+    }
+
+    pub fn dispatch(dispatch: &Dispatch) -> Result<(), CommandError> {
+        smol::block_on(Self::dispatch_async(dispatch))
+    }
 }
 
 macro_rules! command {
@@ -62,15 +139,87 @@ macro_rules! command {
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct Workspace {
-    pub id: u32,
+    /// Positive for regular workspaces. Special workspaces are numbered from
+    /// -99 up, named ones from -1337 down.
+    pub id: i32,
     pub name: String,
     pub windows: usize,
+    /// Name of the monitor the workspace is on
+    pub monitor: String,
+    #[serde(rename = "hasfullscreen")]
+    pub has_fullscreen: bool,
+}
+
+/// How monitors and clients refer to a workspace
+#[derive(Clone, Debug, Deserialize)]
+pub struct WorkspaceRef {
+    pub id: i32,
+    pub name: String,
+}
+
+impl Workspace {
+    /// Whether this is a special workspace, like the scratchpad
+    pub fn is_special(&self) -> bool {
+        is_special(self.id)
+    }
+}
+
+impl WorkspaceRef {
+    /// Whether this is a special workspace, like the scratchpad
+    pub fn is_special(&self) -> bool {
+        is_special(self.id)
+    }
+}
+
+/// The ids Hyprland gives special workspaces
+fn is_special(id: i32) -> bool {
+    (-99..=-2).contains(&id)
 }
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct Client {
+    /// Like `0x5581a3c1e0a0`, for dispatchers to pick this window
+    pub address: String,
     pub class: String,
     pub title: String,
+    pub workspace: WorkspaceRef,
+    /// Addresses of the windows in its group, in tab order, or empty if it
+    /// isn't in one
+    #[serde(default)]
+    pub grouped: Vec<String>,
+    /// 0 for the focused client, counting up the longer ago it had focus
+    #[serde(rename = "focusHistoryID")]
+    pub focus_history_id: i32,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Monitor {
+    pub id: i32,
+    /// The connector name, e.g. `DP-1`
+    pub name: String,
+    pub focused: bool,
+    #[serde(rename = "activeWorkspace")]
+    pub active_workspace: WorkspaceRef,
+    /// The special workspace open on top, with id 0 if there is none
+    #[serde(rename = "specialWorkspace")]
+    pub special_workspace: WorkspaceRef,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Keyboard {
+    pub name: String,
+    /// The full name of the layout in use, like `English (US)`
+    pub active_keymap: String,
+    /// Whether this is the keyboard Hyprland takes as the current one. Older
+    /// versions don't say.
+    #[serde(default)]
+    pub main: bool,
+}
+
+/// The input devices Hyprland knows about
+#[derive(Clone, Debug, Deserialize)]
+pub struct DeviceList {
+    pub keyboards: Vec<Keyboard>,
 }
 
 command!(
@@ -78,4 +227,6 @@ command!(
     Clients("clients") => Vec<Client>,
     ActiveWindow("activewindow") => Client,
     ActiveWorkspace("activeworkspace") => Workspace,
+    Monitors("monitors") => Vec<Monitor>,
+    Devices("devices") => DeviceList,
 );
@@ -20,6 +20,9 @@ pub enum CommandError {
     #[error("Failed to send command to thread")]
     SendFailed,
 
+    #[error("Hyprland refused the dispatcher: {0}")]
+    Dispatch(String),
+
     #[error("At {location}: Receive error: {source}")]
     Recv {
         #[from]
@@ -4,7 +4,10 @@ pub trait Event: Sized + Send + Sync + 'static {
     const NAME: &'static str;
     type Data: Send + Sync + 'static;
 
-    fn parse_data(elements: &[&str]) -> Result<Self::Data, EventParseError>;
+    /// Parse the data after `>>`. Fields are split on commas, except for the
+    /// last one, which takes the rest of the line since titles can have
+    /// commas too.
+    fn parse_data(data: &str) -> Result<Self::Data, EventParseError>;
 }
 
 macro_rules! count_idents {
@@ -36,7 +39,12 @@ macro_rules! event {
             const NAME: &'static str = $name;
             type Data = $data;
 
-            fn parse_data(elements: &[&str]) -> Result<Self::Data, EventParseError> {
+            fn parse_data(data: &str) -> Result<Self::Data, EventParseError> {
+                let elements = data
+                    .splitn(count_idents!($($field),*), ',')
+                    .map(str::trim)
+                    .collect::<Vec<_>>();
+
                 if elements.len() != count_idents!($($field),*) {
                     return Err(EventParseError::InvalidData);
                 }
@@ -73,10 +81,40 @@ event!(
     "workspace" as Workspace >> {
         name: String
     } as WorkspaceData,
+    "workspacev2" as WorkspaceV2 >> {
+        id: i32,
+        name: String,
+    } as WorkspaceV2Data,
+    "createworkspacev2" as CreateWorkspace >> {
+        id: i32,
+        name: String,
+    } as CreateWorkspaceData,
+    "destroyworkspacev2" as DestroyWorkspace >> {
+        id: i32,
+        name: String,
+    } as DestroyWorkspaceData,
+    "moveworkspacev2" as MoveWorkspace >> {
+        id: i32,
+        name: String,
+        monitor: String,
+    } as MoveWorkspaceData,
+    "renameworkspace" as RenameWorkspace >> {
+        id: i32,
+        name: String,
+    } as RenameWorkspaceData,
+    "activespecial" as ActiveSpecial >> {
+        // empty when the special workspace was closed
+        name: String,
+        monitor: String,
+    } as ActiveSpecialData,
     "activewindow" as ActiveWindow >> {
         class: String,
         title: String,
     } as ActiveWindowData,
+    "activewindowv2" as ActiveWindowV2 >> {
+        // without the `0x` that clients have, empty when no window has focus
+        address: String,
+    } as ActiveWindowV2Data,
     "openwindow" as OpenWindow >> {
         address: String,
         name: String,
@@ -86,8 +124,57 @@ event!(
     "closewindow" as CloseWindow >> {
         address: String,
     } as CloseWindowData,
+    "windowtitle" as WindowTitle >> {
+        address: String,
+    } as WindowTitleData,
+    "windowtitlev2" as WindowTitleV2 >> {
+        address: String,
+        title: String,
+    } as WindowTitleV2Data,
+    "togglegroup" as ToggleGroup >> {
+        // 1 when a group was made, 0 when one was taken apart
+        state: u8,
+        // the addresses of its windows, separated by commas
+        addresses: String,
+    } as ToggleGroupData,
+    "moveintogroup" as MoveIntoGroup >> {
+        address: String,
+    } as MoveIntoGroupData,
+    "moveoutofgroup" as MoveOutOfGroup >> {
+        address: String,
+    } as MoveOutOfGroupData,
     "movewindow" as MoveWindow >> {
         address: String,
         workspace: String,
     } as MoveWindowData,
+    "movewindowv2" as MoveWindowV2 >> {
+        address: String,
+        id: i32,
+        name: String,
+    } as MoveWindowV2Data,
+    "focusedmon" as FocusedMonitor >> {
+        monitor: String,
+        workspace: String,
+    } as FocusedMonitorData,
+    "urgent" as Urgent >> {
+        // without the `0x` that clients have
+        address: String,
+    } as UrgentData,
+    "activelayout" as ActiveLayout >> {
+        keyboard: String,
+        layout: String,
+    } as ActiveLayoutData,
+    "submap" as Submap >> {
+        // empty when back in the default submap
+        name: String,
+    } as SubmapData,
+    "fullscreen" as Fullscreen >> {
+        state: u8,
+    } as FullscreenData,
+    "monitoradded" as MonitorAdded >> {
+        name: String,
+    } as MonitorAddedData,
+    "monitorremoved" as MonitorRemoved >> {
+        name: String,
+    } as MonitorRemovedData,
 );
@@ -0,0 +1,35 @@
+use std::{env, fs, os::unix::net::UnixStream, path::PathBuf};
+
+/// The directory holding the sockets of every running Hyprland instance
+fn runtime_dir() -> Result<PathBuf, env::VarError> {
+    Ok(PathBuf::from(env::var("XDG_RUNTIME_DIR")?).join("hypr"))
+}
+
+/// The path of one of the sockets of the instance named by
+/// `HYPRLAND_INSTANCE_SIGNATURE`
+pub fn socket(name: &str) -> Result<PathBuf, env::VarError> {
+    let his = env::var("HYPRLAND_INSTANCE_SIGNATURE")?;
+    Ok(runtime_dir()?.join(his).join(name))
+}
+
+/// Whether the instance with signature `his` is accepting commands
+pub fn is_running(his: &str) -> bool {
+    runtime_dir()
+        .map(|dir| UnixStream::connect(dir.join(his).join(".socket.sock")).is_ok())
+        .unwrap_or(false)
+}
+
+/// The signature of the most recently started instance that is accepting
+/// commands, for when we were started outside of the Hyprland session
+pub fn discover() -> Option<String> {
+    fs::read_dir(runtime_dir().ok()?)
+        .ok()?
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let modified = entry.metadata().ok()?.modified().ok()?;
+            Some((modified, entry.file_name().into_string().ok()?))
+        })
+        .filter(|(_, his)| is_running(his))
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, his)| his)
+}
@@ -1,4 +1,4 @@
-#![feature(error_generic_member_access, downcast_unchecked, never_type)]
+#![feature(error_generic_member_access, downcast_unchecked)]
 
 extern crate smol;
 extern crate thiserror;
@@ -6,4 +6,5 @@ extern crate thiserror;
 pub mod command;
 pub mod error;
 pub mod event;
+pub mod instance;
 pub mod listener;
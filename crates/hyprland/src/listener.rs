@@ -1,6 +1,8 @@
-use std::{any::Any, collections::HashMap, env, sync::Arc, thread};
+use std::{any::Any, collections::HashMap, sync::Arc, thread};
 
 use smol::{
+    channel::{self, Receiver, Sender},
+    future::{self, FutureExt},
     io::{AsyncBufReadExt, BufReader},
     net::unix::UnixStream,
 };
@@ -8,6 +10,7 @@ use smol::{
 use crate::{
     error::{EventParseError, ListenError},
     event::Event,
+    instance,
 };
 
 type AnyData = Arc<dyn Any + Send + Sync>;
@@ -15,12 +18,12 @@ type Callback = Arc<dyn Fn(AnyData) + Send + Sync>;
 
 pub struct AnyEventStore {
     functions: Vec<Callback>,
-    parser: fn(&[&str]) -> Result<AnyData, EventParseError>,
+    parser: fn(&str) -> Result<AnyData, EventParseError>,
 }
 
 impl AnyEventStore {
     pub fn new<E: Event>() -> Self {
-        let parser = |data: &[&str]| E::parse_data(data).map(|data| Arc::new(data) as AnyData);
+        let parser = |data: &str| E::parse_data(data).map(|data| Arc::new(data) as AnyData);
 
         Self {
             functions: Vec::new(),
@@ -32,11 +35,19 @@ impl AnyEventStore {
         self.functions.push(f);
     }
 
-    pub fn call(&self, data: &[&str]) -> Result<(), EventParseError> {
+    /// Call every function with the parsed `data`, each on its own thread
+    /// unless `ordered`
+    pub fn call(&self, data: &str, ordered: bool) -> Result<(), EventParseError> {
         let data = (self.parser)(data)?;
 
         for f in &self.functions {
             let data = Arc::clone(&data);
+
+            if ordered {
+                f(data);
+                continue;
+            }
+
             let f = Arc::clone(f);
             thread::spawn(move || f(data));
         }
@@ -45,9 +56,17 @@ impl AnyEventStore {
     }
 }
 
+/// Stops the [`EventListener`] it came with once dropped
+pub struct ListenerHandle {
+    _stop: Sender<()>,
+}
+
 #[derive(Default)]
 pub struct EventListener {
     events: HashMap<&'static str, AnyEventStore>,
+    /// Closes when the listener should stop
+    stop: Option<Receiver<()>>,
+    ordered: bool,
 }
 
 impl EventListener {
@@ -55,6 +74,26 @@ impl EventListener {
         Self::default()
     }
 
+    /// A listener that stops listening once the handle is dropped, for
+    /// listeners that shouldn't outlive what they feed
+    pub fn with_handle() -> (Self, ListenerHandle) {
+        let (tx, rx) = channel::bounded(1);
+        let listener = Self {
+            events: HashMap::new(),
+            stop: Some(rx),
+            ordered: false,
+        };
+
+        (listener, ListenerHandle { _stop: tx })
+    }
+
+    /// Call the functions one after another on the listening thread rather
+    /// than each on its own, so they see events in the order Hyprland sent
+    /// them. A slow function then holds up every event after it.
+    pub fn set_ordered(&mut self, ordered: bool) {
+        self.ordered = ordered;
+    }
+
     pub fn register<E: Event>(&mut self, f: impl Fn(&E::Data) + Send + Sync + 'static) {
         let name = E::NAME;
         let store = self
@@ -69,35 +108,49 @@ impl EventListener {
         }));
     }
 
-    pub fn listen(self) -> Result<!, ListenError> {
-        let his = env::var("HYPRLAND_INSTANCE_SIGNATURE")?;
-        let xrd = env::var("XDG_RUNTIME_DIR")?;
-        let socket = format!("{xrd}/hypr/{his}/.socket2.sock");
+    /// Call the registered functions for every event, until the handle from
+    /// [`EventListener::with_handle`] is dropped, if there is one
+    pub fn listen(self) -> Result<(), ListenError> {
+        let socket = instance::socket(".socket2.sock")?;
 
-        smol::block_on::<Result<!, ListenError>>(async move {
+        smol::block_on(async move {
             let stream = UnixStream::connect(&socket).await?;
             let mut reader = BufReader::new(stream);
 
             loop {
                 let mut buf = vec![];
-                reader.read_until(b'\n', &mut buf).await?;
+                let read = async { reader.read_until(b'\n', &mut buf).await.map(Some) };
+                let stopped = async {
+                    // nothing is ever sent, the channel only closes
+                    match &self.stop {
+                        Some(stop) => {
+                            let _ = stop.recv().await;
+                        }
+                        None => future::pending().await,
+                    }
+
+                    Ok(None)
+                };
+
+                if read.or(stopped).await?.is_none() {
+                    return Ok(());
+                }
 
                 let event = String::from_utf8(buf)?;
                 let event = event.trim();
-                let &[event, data] = event.split(">>").collect::<Vec<_>>().as_slice() else {
+                // the data can have `>>` in it too, like in window titles
+                let Some((event, data)) = event.split_once(">>") else {
                     return Err(EventParseError::InvalidData)?;
                 };
 
-                let data_parts = data.split(',').map(str::trim).collect::<Vec<_>>();
-
-                self.send(event, &data_parts)?;
+                self.send(event, data)?;
             }
         })
     }
 
-    fn send(&self, event: &str, data: &[&str]) -> Result<(), EventParseError> {
+    fn send(&self, event: &str, data: &str) -> Result<(), EventParseError> {
         if let Some(store) = self.events.get(event) {
-            store.call(data)?;
+            store.call(data, self.ordered)?;
         }
 
         Ok(())
@@ -1,5 +1,6 @@
 #![allow(clippy::missing_safety_doc)]
 
+extern crate serde;
 extern crate smol;
 extern crate thiserror;
 
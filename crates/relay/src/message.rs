@@ -1,21 +1,158 @@
-use strum::{Display, EnumString};
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 use super::{RelayReceiver, RelaySender};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseMessageError {
+    #[error("Empty message")]
+    Empty,
+    #[error("Unknown verb: {0}")]
+    UnknownVerb(String),
+    #[error("Invalid arguments for {0}")]
+    InvalidArguments(String),
+}
+
+/// A request sent over the IPC socket. On the wire it is a single line made of
+/// a verb followed by its arguments, e.g. `toggle center`.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum IpcMessage {
-    #[strum(serialize = "inspector")]
     StartInspector,
-    #[strum(serialize = "reload-css")]
     ReloadCSS,
+    Quit,
+    Status,
+    Toggle(String),
+    /// Change log levels, takes directives like `gnyprland_ui::bar=debug` or
+    /// `reset`
+    LogLevel(String),
+    Logs {
+        follow: bool,
+    },
+    /// Change a setting of the `[bar]` config section until the config is
+    /// reloaded, e.g. `bar set edge left`
+    SetBar {
+        key: String,
+        value: String,
+    },
+    /// Hide or show the bars
+    ToggleBar,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Display)]
+impl IpcMessage {
+    pub const VERBS: &[&str] = &[
+        "inspector",
+        "reload-css",
+        "quit",
+        "status",
+        "toggle",
+        "log-level",
+        "logs",
+        "bar",
+    ];
+}
+
+impl fmt::Display for IpcMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IpcMessage::StartInspector => write!(f, "inspector"),
+            IpcMessage::ReloadCSS => write!(f, "reload-css"),
+            IpcMessage::Quit => write!(f, "quit"),
+            IpcMessage::Status => write!(f, "status"),
+            IpcMessage::Toggle(overlay) => write!(f, "toggle {overlay}"),
+            IpcMessage::LogLevel(directives) => write!(f, "log-level {directives}"),
+            IpcMessage::Logs { follow: false } => write!(f, "logs"),
+            IpcMessage::Logs { follow: true } => write!(f, "logs follow"),
+            IpcMessage::SetBar { key, value } => write!(f, "bar set {key} {value}"),
+            IpcMessage::ToggleBar => write!(f, "bar toggle"),
+        }
+    }
+}
+
+impl FromStr for IpcMessage {
+    type Err = ParseMessageError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let verb = words.next().ok_or(ParseMessageError::Empty)?;
+        let args = words.collect::<Vec<_>>();
+
+        let message = match (verb, args.as_slice()) {
+            ("inspector", []) => IpcMessage::StartInspector,
+            ("reload-css", []) => IpcMessage::ReloadCSS,
+            ("quit", []) => IpcMessage::Quit,
+            ("status", []) => IpcMessage::Status,
+            ("toggle", [overlay]) => IpcMessage::Toggle(overlay.to_string()),
+            ("log-level", directives) if !directives.is_empty() => {
+                IpcMessage::LogLevel(directives.join(","))
+            }
+            ("logs", []) => IpcMessage::Logs { follow: false },
+            ("logs", ["follow"]) => IpcMessage::Logs { follow: true },
+            ("bar", ["toggle"]) => IpcMessage::ToggleBar,
+            ("bar", ["set", key, value @ ..]) if !value.is_empty() => IpcMessage::SetBar {
+                key: key.to_string(),
+                value: value.join(" "),
+            },
+            (verb, _) if Self::VERBS.contains(&verb) => {
+                return Err(ParseMessageError::InvalidArguments(verb.to_string()));
+            }
+            (verb, _) => return Err(ParseMessageError::UnknownVerb(verb.to_string())),
+        };
+
+        Ok(message)
+    }
+}
+
+/// A snapshot of the running bar, returned by [`IpcMessage::Status`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Status {
+    pub pid: u32,
+    pub version: String,
+    pub debug: bool,
+    pub overlay: Option<String>,
+    /// Why the last edit to the config file was rejected, if it was
+    pub config_error: Option<String>,
+}
+
+/// A log record kept by the running bar, returned by [`IpcMessage::Logs`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogRecord {
+    pub time: String,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+}
+
+impl fmt::Display for LogRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {:<5} [{}] {}",
+            self.time, self.level, self.target, self.message
+        )
+    }
+}
+
+/// The reply to an [`IpcMessage`], sent back as JSON. Following the logs
+/// keeps sending [`IpcResponse::Logs`] until either side hangs up.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", content = "data", rename_all = "kebab-case")]
 pub enum IpcResponse {
-    #[strum(to_string = "ok")]
     Ok,
-    #[strum(to_string = "Inspector is only available in debug mode")]
-    InspectorNotAvailable,
+    Status(Status),
+    Logs(Vec<LogRecord>),
+    Error(String),
+}
+
+impl IpcResponse {
+    pub fn error(message: impl fmt::Display) -> Self {
+        IpcResponse::Error(message.to_string())
+    }
+
+    pub fn is_ok(&self) -> bool {
+        !matches!(self, IpcResponse::Error(_))
+    }
 }
 
 pub type IpcSender = RelaySender<IpcMessage, IpcResponse>;
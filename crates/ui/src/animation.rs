@@ -0,0 +1,126 @@
+use std::{cell::RefCell, rc::Rc};
+
+use gnyprland_config::{AnimationConfig, Easing};
+use relm4::gtk::{
+    gdk::FrameClock,
+    glib::{ControlFlow, WeakRef},
+    TickCallbackId,
+};
+
+use crate::prelude::*;
+
+/// How far along `easing` is after `t`, both from 0 to 1
+fn ease(easing: Easing, t: f64) -> f64 {
+    match easing {
+        Easing::Linear => t,
+        Easing::EaseIn => t * t * t,
+        Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+        Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+    }
+}
+
+/// Whether animations should be skipped, by the config or GTK's settings
+fn reduced(config: &AnimationConfig) -> bool {
+    let enabled = gtk::Settings::default().is_none_or(|s| s.is_gtk_enable_animations());
+    config.reduce || !enabled || config.duration == 0
+}
+
+struct State {
+    config: AnimationConfig,
+    value: f64,
+    from: f64,
+    to: f64,
+    /// Frame time of the first frame, in microseconds
+    start: Option<i64>,
+    tick: Option<TickCallbackId>,
+}
+
+impl State {
+    /// Move `value` along for the frame at `now`, and say whether it's done
+    fn step(&mut self, now: i64) -> bool {
+        let start = *self.start.get_or_insert(now);
+        let duration = self.config.duration as f64 * 1000.0;
+        let t = ((now - start) as f64 / duration).clamp(0.0, 1.0);
+
+        self.value = self.from + (self.to - self.from) * ease(self.config.easing, t);
+        t >= 1.0
+    }
+}
+
+/// A number animated on the frame clock of a widget, which is redrawn on
+/// every frame. Changing the target midway starts over from wherever the
+/// value is.
+#[derive(Clone)]
+pub struct Animation {
+    /// Weak, since the widget usually holds the animation in its draw func
+    widget: WeakRef<gtk::Widget>,
+    state: Rc<RefCell<State>>,
+}
+
+impl Animation {
+    pub fn new(widget: &impl IsA<gtk::Widget>, value: f64, config: AnimationConfig) -> Self {
+        Self {
+            widget: widget.upcast_ref::<gtk::Widget>().downgrade(),
+            state: Rc::new(RefCell::new(State {
+                config,
+                value,
+                from: value,
+                to: value,
+                start: None,
+                tick: None,
+            })),
+        }
+    }
+
+    pub fn value(&self) -> f64 {
+        self.state.borrow().value
+    }
+
+    /// Animate from the current value to `to`
+    pub fn animate_to(&self, to: f64) {
+        let Some(widget) = self.widget.upgrade() else {
+            return;
+        };
+
+        let mut state = self.state.borrow_mut();
+
+        state.from = state.value;
+        state.to = to;
+        state.start = None;
+
+        if reduced(&state.config) {
+            state.value = to;
+
+            if let Some(tick) = state.tick.take() {
+                tick.remove();
+            }
+
+            widget.queue_draw();
+            return;
+        }
+
+        if state.tick.is_some() {
+            return;
+        }
+
+        let weak = Rc::downgrade(&self.state);
+        let tick = widget.add_tick_callback(move |widget, clock: &FrameClock| {
+            let Some(state) = weak.upgrade() else {
+                return ControlFlow::Break;
+            };
+
+            let mut state = state.borrow_mut();
+            let done = state.step(clock.frame_time());
+            widget.queue_draw();
+
+            if done {
+                state.tick = None;
+                ControlFlow::Break
+            } else {
+                ControlFlow::Continue
+            }
+        });
+
+        state.tick = Some(tick);
+    }
+}
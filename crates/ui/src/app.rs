@@ -0,0 +1,316 @@
+use std::{
+    collections::{HashMap, HashSet},
+    mem,
+    path::PathBuf,
+    process,
+};
+
+use gnyprland_config::Config;
+use gnyprland_relay::{
+    message::{IpcMessage, IpcReceiver, IpcResponse, Status},
+    RelayResponder,
+};
+use hyprland::{
+    command::{Executor, Monitors},
+    event,
+};
+use relm4::gtk::{gdk, CssProvider};
+
+use crate::{
+    bar::{self, Bar},
+    config, css, desktop,
+    overlays::{self, active::ActiveOverlay},
+    prelude::*,
+    shutdown, systemd,
+};
+
+#[derive(Clone, Debug)]
+pub enum Message {
+    Ipc(IpcMessage),
+    ReloadCSS,
+    Config(Config),
+    ConfigError(String),
+    SyncMonitors,
+}
+
+/// Owns everything shared between the bars, and keeps one bar on every
+/// monitor. Its own window is never shown.
+pub struct App {
+    responder: RelayResponder<IpcResponse>,
+    css: gtk::CssProvider,
+
+    config: Config,
+    config_error: Option<String>,
+
+    /// Keyed by connector name
+    bars: HashMap<String, Controller<Bar>>,
+}
+
+/// The connected monitors that Hyprland knows about, by connector name
+fn monitors() -> Vec<(String, gdk::Monitor)> {
+    let hyprland = match Executor::command::<Monitors>() {
+        Ok(monitors) => Some(monitors.into_iter().map(|m| m.name).collect::<HashSet<_>>()),
+        Err(e) => {
+            warn!("Failed to list Hyprland's monitors, using all of them: {e}");
+            None
+        }
+    };
+
+    let list = gdk::Display::default().unwrap().monitors();
+
+    (0..list.n_items())
+        .filter_map(|i| list.item(i).and_downcast::<gdk::Monitor>())
+        .filter_map(|monitor| Some((monitor.connector()?.to_string(), monitor)))
+        .filter(|(name, _)| hyprland.as_ref().is_none_or(|names| names.contains(name)))
+        .collect()
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for App {
+    type Init = (IpcReceiver, Config, PathBuf);
+    type Input = Message;
+    type Output = ();
+
+    view! {
+        gtk::Window {
+            set_title: Some("gnyprland"),
+        }
+    }
+
+    fn init(
+        (mut receiver, config, config_path): Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        // registered first so it runs last, once the other hooks have logged
+        shutdown::on_shutdown("logs", || log::logger().flush());
+
+        cfg_if! {
+            if #[cfg(debug_assertions)] {
+                debug!("Watching for CSS changes");
+                let sender_clone = sender.clone();
+                css::begin_watch(move || sender_clone.input_sender().emit(Message::ReloadCSS));
+            } else {
+                css::write_css().unwrap();
+                sender.input_sender().emit(Message::ReloadCSS);
+            }
+        }
+
+        let sender_clone = sender.clone();
+        config::begin_watch(config_path, move |res| match res {
+            Ok(config) => sender_clone.input(Message::Config(config)),
+            Err(e) => sender_clone.input(Message::ConfigError(e.to_string())),
+        });
+
+        desktop::watch();
+
+        // gdk and Hyprland don't agree on when a monitor comes or goes, so
+        // listen to both and sync up each time
+        gdk::Display::default()
+            .unwrap()
+            .monitors()
+            .connect_items_changed(clone!(
+                #[strong]
+                sender,
+                move |_, _, _, _| sender.input(Message::SyncMonitors)
+            ));
+
+        thread::spawn(clone!(
+            #[strong]
+            sender,
+            move || {
+                let mut listener = EventListener::new();
+
+                listener.register::<event::MonitorAdded>(clone!(
+                    #[strong]
+                    sender,
+                    move |monitor| {
+                        debug!("Monitor added: {}", monitor.name);
+                        sender.input(Message::SyncMonitors);
+                    }
+                ));
+
+                listener.register::<event::MonitorRemoved>(clone!(
+                    #[strong]
+                    sender,
+                    move |monitor| {
+                        debug!("Monitor removed: {}", monitor.name);
+                        sender.input(Message::SyncMonitors);
+                    }
+                ));
+
+                debug!("Watching for monitor changes");
+                listener.listen().unwrap()
+            }
+        ));
+
+        // setup return values
+        let css = CssProvider::new();
+        let widgets = view_output!();
+        let mut model = App {
+            responder: receiver.responder(),
+            css,
+            config,
+            config_error: None,
+            bars: HashMap::new(),
+        };
+
+        // register css provider
+        gtk::style_context_add_provider_for_display(
+            &gdk::Display::default().unwrap(),
+            &model.css,
+            gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
+        );
+
+        model.sync_monitors();
+
+        // forward messages
+        smol::spawn(async move {
+            while let Ok(message) = receiver.receive().await {
+                sender.input_sender().emit(Message::Ipc(message));
+            }
+        })
+        .detach();
+
+        overlays::attach_windows();
+        shutdown::handle_signals();
+
+        systemd::start_watchdog();
+        systemd::ready();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, message: Self::Input, _: ComponentSender<Self>) {
+        match message {
+            Message::Ipc(ipc) => {
+                let response = match ipc {
+                    IpcMessage::StartInspector => {
+                        cfg_if! {
+                            if #[cfg(debug_assertions)] {
+                                debug!("Toggling inspector");
+                                gtk::Window::set_interactive_debugging(true);
+                                let response = IpcResponse::Ok;
+                            } else {
+                                warn!("Inspector is only available in debug mode");
+                                let response = IpcResponse::error("Inspector is only available in debug mode");
+                            }
+                        }
+
+                        response
+                    }
+                    IpcMessage::ReloadCSS => {
+                        debug!("Reloading CSS");
+                        self.css.load_from_path(css::FILE);
+                        IpcResponse::Ok
+                    }
+                    IpcMessage::Quit => {
+                        info!("Quitting on request");
+                        smol::block_on(self.responder.respond(IpcResponse::Ok)).unwrap();
+                        shutdown::shutdown();
+                        return;
+                    }
+                    IpcMessage::Status => IpcResponse::Status(Status {
+                        pid: process::id(),
+                        version: env!("CARGO_PKG_VERSION").to_string(),
+                        debug: cfg!(debug_assertions),
+                        overlay: ActiveOverlay::current().map(|o| o.name().to_string()),
+                        config_error: self.config_error.clone(),
+                    }),
+                    IpcMessage::Toggle(name) => match ActiveOverlay::from_name(&name) {
+                        Some(overlay) => {
+                            debug!("Toggling overlay {name}");
+                            ActiveOverlay::toggle(overlay);
+                            IpcResponse::Ok
+                        }
+                        None => IpcResponse::error(format!("Unknown overlay: {name}")),
+                    },
+                    IpcMessage::SetBar { key, value } => {
+                        let mut config = self.config.clone();
+
+                        match config.set_bar(&key, &value) {
+                            Ok(()) => {
+                                debug!("Setting bar.{key} to {value}");
+                                self.apply_config(config);
+                                IpcResponse::Ok
+                            }
+                            Err(e) => IpcResponse::error(e.to_string().trim_end()),
+                        }
+                    }
+                    IpcMessage::ToggleBar => {
+                        for bar in self.bars.values() {
+                            bar.emit(bar::Message::Toggle);
+                        }
+
+                        IpcResponse::Ok
+                    }
+                    // logging belongs to the process, so the server answers these
+                    IpcMessage::LogLevel(_) | IpcMessage::Logs { .. } => {
+                        IpcResponse::error("Logging is handled by the server")
+                    }
+                };
+
+                smol::block_on(self.responder.respond(response)).unwrap();
+            }
+            Message::ReloadCSS => {
+                debug!("Reloading CSS");
+                self.css.load_from_path(css::FILE);
+            }
+            Message::Config(config) => {
+                self.config_error = None;
+                self.apply_config(config);
+            }
+            Message::ConfigError(e) => {
+                error!("Keeping the current config: {e}");
+                self.config_error = Some(e);
+            }
+            Message::SyncMonitors => self.sync_monitors(),
+        }
+    }
+}
+
+impl App {
+    /// Hand every bar its part of `config`
+    fn apply_config(&mut self, config: Config) {
+        if mem::replace(&mut self.config, config) == self.config {
+            return;
+        }
+
+        info!("Applying the new config");
+
+        for (name, bar) in &self.bars {
+            bar.emit(bar::Message::Config(self.config.for_monitor(name)));
+        }
+    }
+
+    /// Add bars to new monitors and remove those of disconnected ones
+    fn sync_monitors(&mut self) {
+        let monitors = monitors();
+
+        self.bars.retain(|name, bar| {
+            let connected = monitors.iter().any(|(n, _)| n == name);
+
+            if !connected {
+                info!("Removing the bar from {name}");
+                bar.widget().destroy();
+            }
+
+            connected
+        });
+
+        for (name, monitor) in monitors {
+            if self.bars.contains_key(&name) {
+                continue;
+            }
+
+            info!("Adding a bar to {name}");
+
+            let builder = Bar::builder();
+            relm4::main_application().add_window(&builder.root);
+
+            let config = self.config.for_monitor(&name);
+            let bar = builder.launch((name.clone(), monitor, config)).detach();
+            self.bars.insert(name, bar);
+        }
+    }
+}
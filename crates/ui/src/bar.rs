@@ -1,162 +1,365 @@
 mod datetime;
+mod keyboard;
+mod modules;
+mod submap;
+mod taskbar;
 mod window;
 mod workspace;
 
-use datetime::DateTime;
-use gnyprland_relay::{
-    message::{IpcMessage, IpcReceiver, IpcResponse},
-    RelayResponder,
+use std::{mem, sync::Arc};
+
+use gnyprland_config::{BarEdge, BarLayer, Config, FullscreenMode};
+use hyprland::{
+    command::{Dispatch, Executor, Monitors, Workspaces},
+    error::CommandError,
+    event,
 };
-use relm4::gtk::{gdk::Display, CssProvider};
-use window::ActiveWindow;
-use workspace::ActiveWorkspace;
+use modules::{ModuleContext, Slot};
+use relm4::gtk::{gdk, glib, EventControllerMotion, Orientation};
+
+use crate::prelude::*;
 
-use crate::{css, overlays, prelude::*};
+/// How thick an autohiding bar is while hidden, enough to hover
+const COLLAPSED_SIZE: i32 = 2;
 
-const HEIGHT: i32 = 57;
+/// How long an autohiding bar stays after the pointer leaves it
+const CONCEAL_DELAY: Duration = Duration::from_millis(500);
 
 #[derive(Clone, Debug)]
 pub enum Message {
-    Ipc(IpcMessage),
-    ReloadCSS,
+    Config(Config),
+    Toggle,
+    Fullscreen(bool),
+    Hover(bool),
+    /// Hide an autohiding bar again, unless the pointer came back since
+    Conceal(u32),
 }
 
-#[allow(dead_code)]
+/// Whether the workspace shown on `monitor` has a fullscreen window
+fn fullscreen_on(monitor: &str) -> bool {
+    let fullscreen = || -> Result<bool, CommandError> {
+        let Some(workspace) = Executor::command::<Monitors>()?
+            .into_iter()
+            .find(|m| m.name == monitor)
+            .map(|m| m.active_workspace.id)
+        else {
+            return Ok(false);
+        };
+
+        Ok(Executor::command::<Workspaces>()?
+            .into_iter()
+            .any(|w| w.id == workspace && w.has_fullscreen))
+    };
+
+    fullscreen().unwrap_or_else(|e| {
+        warn!("Failed to look for fullscreen windows on {monitor}: {e}");
+        false
+    })
+}
+
+/// Run `dispatch` for a module, which mostly fails when whatever it acts on
+/// went away in the meantime
+fn dispatch(dispatch: Dispatch) {
+    if let Err(e) = Executor::dispatch(&dispatch) {
+        warn!("Failed to dispatch `{dispatch}`: {e}");
+    }
+}
+
+/// The bar on a single monitor
 pub struct Bar {
-    responder: RelayResponder<IpcResponse>,
-    css: gtk::CssProvider,
+    /// Connector name of the monitor
+    monitor: String,
+    config: Config,
+
+    window: gtk::Window,
+    layout: gtk::CenterBox,
+    start: Slot,
+    center: Slot,
+    end: Slot,
 
-    active_window: Controller<ActiveWindow>,
-    active_workspace: Controller<ActiveWorkspace>,
-    datetime: Controller<DateTime>,
+    /// Hidden over IPC
+    hidden: bool,
+    fullscreen: bool,
+    /// Whether an autohiding bar is showing
+    revealed: bool,
+    /// Bumped on every hover change, so stale conceal timers do nothing
+    hover: u32,
+    /// Stops watching for fullscreen windows once the bar is gone
+    _listener: ListenerHandle,
 }
 
 #[relm4::component(pub)]
 impl SimpleComponent for Bar {
-    type Init = IpcReceiver;
+    type Init = (String, gdk::Monitor, Config);
     type Input = Message;
     type Output = ();
 
     view! {
         main_window = gtk::Window {
             set_title: Some("panel"),
-            set_default_height: HEIGHT,
 
             init_layer_shell: (),
-            set_layer: Layer::Bottom,
-            set_anchor: (Edge::Top, true),
-            set_anchor: (Edge::Right, true),
-            set_anchor: (Edge::Left, true),
-            set_exclusive_zone: HEIGHT,
-
-            set_hexpand: true,
-            set_css_classes: &["bar"],
+            set_monitor: Some(&gdk_monitor),
 
+            #[name = "layout"]
             gtk::CenterBox {
+                #[name = "start"]
                 #[wrap(Some)]
-                set_start_widget = &gtk::Box {
-                    set_spacing: 8,
-
-                    #[local_ref]
-                    active_window_widget -> root!(ActiveWindow),
+                set_start_widget = &gtk::Box {},
 
-                    #[local_ref]
-                    active_workspace_widget -> root!(ActiveWorkspace),
-                },
+                #[name = "center"]
+                #[wrap(Some)]
+                set_center_widget = &gtk::Box {},
 
-                #[local_ref]
+                #[name = "end"]
                 #[wrap(Some)]
-                set_center_widget = datetime_widget -> root!(DateTime),
+                set_end_widget = &gtk::Box {},
             }
         }
     }
 
     fn init(
-        mut init: Self::Init,
+        (monitor, gdk_monitor, config): Self::Init,
         root: Self::Root,
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
-        cfg_if! {
-            if #[cfg(debug_assertions)] {
-                debug!("Watching for CSS changes");
-                let sender_clone = sender.clone();
-                css::begin_watch(move || sender_clone.input_sender().emit(Message::ReloadCSS));
-            } else {
-                css::write_css().unwrap();
-                sender.input_sender().emit(Message::ReloadCSS);
-            }
-        }
+        let motion = EventControllerMotion::new();
+        motion.connect_enter(clone!(
+            #[strong]
+            sender,
+            move |_, _, _| sender.input(Message::Hover(true))
+        ));
+        motion.connect_leave(clone!(
+            #[strong]
+            sender,
+            move |_| sender.input(Message::Hover(false))
+        ));
+        root.add_controller(motion);
 
-        let active_window = ActiveWindow::builder().launch(()).detach();
+        let fullscreen = fullscreen_on(&monitor);
+        let (mut listener, handle) = EventListener::with_handle();
 
-        let active_workspace = ActiveWorkspace::builder().launch(()).detach();
-        let datetime = DateTime::builder()
-            .launch(())
-            .forward(sender.input_sender(), identity);
+        thread::spawn(clone!(
+            #[strong]
+            monitor,
+            move || {
+                let update = move || sender.input(Message::Fullscreen(fullscreen_on(&monitor)));
+                let update = Arc::new(update);
 
-        let active_window_widget = active_window.widget();
-        let active_workspace_widget = active_workspace.widget();
-        let datetime_widget = datetime.widget();
+                // a workspace with a fullscreen window can also come into view
+                // or lose it by closing or moving the window
+                listener.register::<event::Fullscreen>(clone!(
+                    #[strong]
+                    update,
+                    move |_| update()
+                ));
+                listener.register::<event::Workspace>(clone!(
+                    #[strong]
+                    update,
+                    move |_| update()
+                ));
+                listener.register::<event::FocusedMonitor>(clone!(
+                    #[strong]
+                    update,
+                    move |_| update()
+                ));
+                listener.register::<event::CloseWindow>(clone!(
+                    #[strong]
+                    update,
+                    move |_| update()
+                ));
+                listener.register::<event::MoveWindow>(move |_| update());
+
+                debug!("Watching for fullscreen windows");
+                listener.listen().unwrap()
+            }
+        ));
 
         // setup return values
-        let css = CssProvider::new();
         let widgets = view_output!();
-        let model = Bar {
-            responder: init.responder(),
-            css,
-            active_window,
-            active_workspace,
-            datetime,
+        let mut model = Bar {
+            monitor,
+            config,
+            window: root.clone(),
+            layout: widgets.layout.clone(),
+            start: Slot::new(widgets.start.clone()),
+            center: Slot::new(widgets.center.clone()),
+            end: Slot::new(widgets.end.clone()),
+            hidden: false,
+            fullscreen,
+            revealed: false,
+            hover: 0,
+            _listener: handle,
         };
 
-        // register css provider
-        gtk::style_context_add_provider_for_display(
-            &Display::default().unwrap(),
-            &model.css,
-            gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
-        );
-
-        // forward messages
-        smol::spawn(async move {
-            while let Ok(message) = init.receive().await {
-                sender.input_sender().emit(Message::Ipc(message));
-            }
-        })
-        .detach();
+        model.place();
 
-        overlays::attach_windows();
+        let ctx = ModuleContext {
+            config: &model.config,
+            monitor: &model.monitor,
+        };
+        model.start.fill(&model.config.layout.start, &ctx);
+        model.center.fill(&model.config.layout.center, &ctx);
+        model.end.fill(&model.config.layout.end, &ctx);
 
         ComponentParts { model, widgets }
     }
 
-    fn update(&mut self, message: Self::Input, _: ComponentSender<Self>) {
+    fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
         match message {
-            Message::Ipc(ipc) => {
-                match ipc {
-                    IpcMessage::StartInspector => {
-                        cfg_if! {
-                            if #[cfg(debug_assertions)] {
-                                debug!("Toggling inspector");
-                                gtk::Window::set_interactive_debugging(true);
-                            } else {
-                                warn!("Inspector is only available in debug mode");
-                                smol::block_on(self.responder.respond(IpcResponse::InspectorNotAvailable)).unwrap();
-                                return;
-                            }
-                        }
-                    }
-                    IpcMessage::ReloadCSS => {
-                        debug!("Reloading CSS");
-                        self.css.load_from_path(css::FILE);
-                    }
-                };
-
-                smol::block_on(self.responder.respond(IpcResponse::Ok)).unwrap();
+            Message::Config(config) => self.apply_config(config),
+            Message::Toggle => {
+                self.hidden = !self.hidden;
+                self.update_visibility();
+            }
+            Message::Fullscreen(fullscreen) => {
+                if self.fullscreen != fullscreen {
+                    trace!("Fullscreen on {}: {fullscreen}", self.monitor);
+                    self.fullscreen = fullscreen;
+                    self.update_visibility();
+                }
+            }
+            Message::Hover(true) => {
+                self.hover += 1;
+
+                if !self.revealed {
+                    self.revealed = true;
+                    self.update_visibility();
+                }
+            }
+            Message::Hover(false) => {
+                self.hover += 1;
+                let hover = self.hover;
+
+                glib::timeout_add_local_once(CONCEAL_DELAY, move || {
+                    sender.input(Message::Conceal(hover));
+                });
             }
-            Message::ReloadCSS => {
-                debug!("Reloading CSS");
-                self.css.load_from_path(css::FILE);
+            Message::Conceal(hover) => {
+                if hover == self.hover && self.revealed {
+                    self.revealed = false;
+                    self.update_visibility();
+                }
             }
         }
     }
 }
+
+fn edge(edge: BarEdge) -> Edge {
+    match edge {
+        BarEdge::Top => Edge::Top,
+        BarEdge::Bottom => Edge::Bottom,
+        BarEdge::Left => Edge::Left,
+        BarEdge::Right => Edge::Right,
+    }
+}
+
+/// Which way the bar, and the modules on it, run along `edge`
+fn orientation(edge: BarEdge) -> Orientation {
+    match edge.is_vertical() {
+        true => Orientation::Vertical,
+        false => Orientation::Horizontal,
+    }
+}
+
+fn layer(layer: BarLayer) -> Layer {
+    match layer {
+        BarLayer::Background => Layer::Background,
+        BarLayer::Bottom => Layer::Bottom,
+        BarLayer::Top => Layer::Top,
+        BarLayer::Overlay => Layer::Overlay,
+    }
+}
+
+impl Bar {
+    /// Put the window where `[bar]` says and lay it out to match, which
+    /// layer-shell lets us redo while the bar is showing
+    fn place(&self) {
+        let bar = &self.config.bar;
+        let window = &self.window;
+
+        // anchoring to both edges across the bar stretches it along its edge
+        for side in [BarEdge::Top, BarEdge::Bottom, BarEdge::Left, BarEdge::Right] {
+            window.set_anchor(edge(side), side != bar.edge.opposite());
+        }
+
+        window.set_layer(layer(bar.layer));
+        window.set_margin(Edge::Top, bar.margin.top);
+        window.set_margin(Edge::Right, bar.margin.right);
+        window.set_margin(Edge::Bottom, bar.margin.bottom);
+        window.set_margin(Edge::Left, bar.margin.left);
+
+        let orientation = orientation(bar.edge);
+
+        self.layout.set_orientation(orientation);
+
+        for slot in [&self.start, &self.center, &self.end] {
+            slot.set_orientation(orientation);
+            slot.set_spacing(self.config.layout.spacing);
+        }
+
+        self.update_visibility();
+    }
+
+    /// Show, hide or collapse the bar, and reserve space for it, depending on
+    /// the config, fullscreen windows, hovering and IPC
+    fn update_visibility(&self) {
+        let bar = &self.config.bar;
+        let window = &self.window;
+
+        let fullscreen = match self.fullscreen {
+            true => bar.fullscreen,
+            false => FullscreenMode::Keep,
+        };
+
+        let hidden = self.hidden || fullscreen == FullscreenMode::Hide;
+        let collapsed = bar.autohide && !self.revealed;
+        let exclusive = bar.exclusive && !bar.autohide && fullscreen == FullscreenMode::Keep;
+        let size = if collapsed { COLLAPSED_SIZE } else { bar.size };
+
+        window.set_exclusive_zone(if exclusive { bar.size } else { 0 });
+
+        if bar.edge.is_vertical() {
+            window.set_size_request(size, -1);
+        } else {
+            window.set_size_request(-1, size);
+        }
+
+        window.set_css_classes(css![
+            "bar",
+            "vertical" if bar.edge.is_vertical(),
+            "collapsed" if collapsed
+        ]);
+        self.layout.set_visible(!collapsed);
+        window.set_visible(!hidden);
+    }
+
+    /// Switch to `config`, rebuilding only the modules whose section changed
+    fn apply_config(&mut self, config: Config) {
+        let old = mem::replace(&mut self.config, config);
+
+        if old == self.config {
+            return;
+        }
+
+        debug!("Applying the new config to {}", self.monitor);
+
+        if old.bar != self.config.bar || old.layout.spacing != self.config.layout.spacing {
+            self.place();
+        }
+
+        let layout = &self.config.layout;
+        let ctx = ModuleContext {
+            config: &self.config,
+            monitor: &self.monitor,
+        };
+
+        for (slot, kinds) in [
+            (&mut self.start, &layout.start),
+            (&mut self.center, &layout.center),
+            (&mut self.end, &layout.end),
+        ] {
+            slot.update(kinds, &old, &ctx);
+        }
+    }
+}
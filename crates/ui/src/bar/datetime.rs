@@ -1,8 +1,17 @@
+use std::sync::mpsc::{self, RecvTimeoutError};
+
 use chrono::{DateTime as DateTimeData, Local, Timelike};
+use gnyprland_config::DateTimeConfig;
 
 use crate::{overlays::active::ActiveOverlay, prelude::*};
 
-fn poll_datetime(mut callback: impl FnMut(DateTimeData<Local>) + Send + 'static) {
+/// Call `callback` now and at the start of every minute, until the returned
+/// sender is dropped
+fn poll_datetime(
+    mut callback: impl FnMut(DateTimeData<Local>) + Send + 'static,
+) -> mpsc::Sender<()> {
+    let (stop, stopped) = mpsc::channel();
+
     thread::spawn(move || loop {
         let now = Local::now();
         callback(now);
@@ -10,8 +19,13 @@ fn poll_datetime(mut callback: impl FnMut(DateTimeData<Local>) + Send + 'static)
         let seconds_remaining = 60 - now.second();
         let nanos_remaining = 1_000_000_000 - now.nanosecond();
         let sleep_duration = Duration::new(seconds_remaining as u64, nanos_remaining);
-        thread::sleep(sleep_duration);
+
+        if let Err(RecvTimeoutError::Disconnected) = stopped.recv_timeout(sleep_duration) {
+            break;
+        }
     });
+
+    stop
 }
 
 #[derive(Clone, Debug)]
@@ -23,13 +37,18 @@ pub enum Message {
 pub struct DateTime {
     time: DateTimeData<Local>,
     open: bool,
+    config: DateTimeConfig,
+    /// Key of the callback following the overlay
+    overlay: usize,
+    /// Stops the polling once dropped
+    _poll: mpsc::Sender<()>,
 }
 
 #[relm4::component(pub)]
 impl SimpleComponent for DateTime {
-    type Init = ();
+    type Init = DateTimeConfig;
     type Input = Message;
-    type Output = super::Message;
+    type Output = ();
 
     view! {
         gtk::Button {
@@ -42,7 +61,7 @@ impl SimpleComponent for DateTime {
 
             gtk::Label {
                 #[watch]
-                set_label: &model.time.format("%A, %b %d  %l:%M %p").to_string(),
+                set_label: &model.time.format(&model.config.format).to_string(),
                 #[watch]
                 set_css_classes: css!["text text-lg"],
             },
@@ -50,20 +69,15 @@ impl SimpleComponent for DateTime {
     }
 
     fn init(
-        _: Self::Init,
+        config: Self::Init,
         root: Self::Root,
         sender: relm4::ComponentSender<Self>,
     ) -> relm4::ComponentParts<Self> {
-        let model = DateTime {
-            time: Local::now(),
-            open: false,
-        };
-
-        ActiveOverlay::on_change(sender.input_sender(), |open| {
+        let overlay = ActiveOverlay::on_change(sender.input_sender(), |open| {
             Message::Open(open == Some(ActiveOverlay::Center))
         });
 
-        poll_datetime(clone!(
+        let poll = poll_datetime(clone!(
             #[strong]
             sender,
             move |dt| {
@@ -71,6 +85,14 @@ impl SimpleComponent for DateTime {
             }
         ));
 
+        let model = DateTime {
+            time: Local::now(),
+            open: false,
+            config,
+            overlay,
+            _poll: poll,
+        };
+
         let widgets = view_output!();
         ComponentParts { model, widgets }
     }
@@ -85,4 +107,8 @@ impl SimpleComponent for DateTime {
             }
         }
     }
+
+    fn shutdown(&mut self, _: &mut Self::Widgets, _: relm4::Sender<Self::Output>) {
+        ActiveOverlay::remove_on_change(self.overlay);
+    }
 }
@@ -0,0 +1,139 @@
+use gnyprland_config::KeyboardConfig;
+use hyprland::{
+    command::{Devices, Dispatch, Executor},
+    error::CommandError,
+    event,
+};
+use relm4::gtk::{gdk, GestureClick};
+
+use super::dispatch;
+use crate::prelude::*;
+
+/// The full name of the layout in use on the keyboard called `device`, or on
+/// the main keyboard if it's empty
+fn layout_of(device: &str) -> Result<Option<String>, CommandError> {
+    let keyboards = Executor::command::<Devices>()?.keyboards;
+
+    let keyboard = match device.is_empty() {
+        true => keyboards
+            .iter()
+            .find(|k| k.main)
+            .or_else(|| keyboards.first()),
+        false => keyboards.iter().find(|k| k.name == device),
+    };
+
+    Ok(keyboard.map(|k| k.active_keymap.clone()))
+}
+
+#[derive(Clone, Debug)]
+pub enum Message {
+    Layout(Option<String>),
+    Switch { prev: bool },
+}
+
+pub struct KeyboardLayout {
+    /// Full name of the layout in use, if the keyboard is there
+    layout: Option<String>,
+    config: KeyboardConfig,
+    _listener: ListenerHandle,
+}
+
+impl KeyboardLayout {
+    fn label(&self) -> String {
+        self.layout
+            .as_deref()
+            .map(|layout| self.config.label(layout))
+            .unwrap_or_default()
+    }
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for KeyboardLayout {
+    type Init = KeyboardConfig;
+    type Input = Message;
+    type Output = ();
+
+    view! {
+        gtk::Box {
+            set_css_classes: &["element", "keyboard-layout"],
+            #[watch]
+            set_visible: model.layout.is_some(),
+            #[watch]
+            set_tooltip_text: model.layout.as_deref(),
+
+            gtk::Label {
+                #[watch]
+                set_label: &model.label(),
+                set_css_classes: &["text"],
+            },
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        // primary click switches to the next layout, secondary to the previous
+        let click = GestureClick::builder().button(0).build();
+        click.connect_released(clone!(
+            #[strong]
+            sender,
+            move |gesture, _, _, _| match gesture.current_button() {
+                gdk::BUTTON_PRIMARY => sender.input(Message::Switch { prev: false }),
+                gdk::BUTTON_SECONDARY => sender.input(Message::Switch { prev: true }),
+                _ => {}
+            }
+        ));
+        root.add_controller(click);
+
+        let (mut listener, handle) = EventListener::with_handle();
+        let model = KeyboardLayout {
+            layout: layout_of(&config.device).unwrap_or_else(|e| {
+                warn!("Failed to look up the keyboard layout: {e}");
+                None
+            }),
+            config,
+            _listener: handle,
+        };
+
+        thread::spawn(clone!(
+            #[strong(rename_to = device)]
+            model.config.device,
+            move || {
+                // the event names the keyboard, but not in a way that tells
+                // whether it's the main one
+                listener.register::<event::ActiveLayout>(move |layout| {
+                    trace!("Layout of {}: {}", layout.keyboard, layout.layout);
+
+                    // the last layout stays up when this fails
+                    match layout_of(&device) {
+                        Ok(layout) => sender.input(Message::Layout(layout)),
+                        Err(e) => warn!("Failed to look up the keyboard layout: {e}"),
+                    }
+                });
+
+                debug!("Watching for keyboard layout changes");
+                listener.listen().unwrap()
+            }
+        ));
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, message: Self::Input, _: ComponentSender<Self>) {
+        match message {
+            Message::Layout(layout) => self.layout = layout,
+            Message::Switch { prev } => {
+                let device = match self.config.device.is_empty() {
+                    true => String::from("current"),
+                    false => self.config.device.clone(),
+                };
+
+                dispatch(Dispatch::SwitchXkbLayout { device, prev });
+            }
+        }
+    }
+}
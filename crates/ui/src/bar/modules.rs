@@ -0,0 +1,165 @@
+use std::mem;
+
+use gnyprland_config::{Config, ModuleKind};
+
+use super::{
+    datetime::DateTime, keyboard::KeyboardLayout, submap::Submap, taskbar::Taskbar,
+    window::ActiveWindow, workspace::ActiveWorkspace,
+};
+use crate::prelude::*;
+
+/// A running module, whatever component is behind it
+pub trait Module {
+    fn widget(&self) -> gtk::Widget;
+}
+
+impl<C> Module for Controller<C>
+where
+    C: Component,
+    C::Root: IsA<gtk::Widget>,
+{
+    fn widget(&self) -> gtk::Widget {
+        ComponentController::widget(self).clone().upcast()
+    }
+}
+
+/// Everything a module gets to build itself from
+pub struct ModuleContext<'a> {
+    pub config: &'a Config,
+    /// Connector name of the bar's monitor, for modules that only show what
+    /// happens on it
+    pub monitor: &'a str,
+}
+
+/// Build the module for `kind`, launched with its own config section
+pub fn build(kind: ModuleKind, ctx: &ModuleContext) -> Box<dyn Module> {
+    let config = ctx.config;
+    let monitor = ctx.monitor.to_string();
+
+    match kind {
+        ModuleKind::Window => Box::new(
+            ActiveWindow::builder()
+                .launch((config.window.clone(), config.workspaces.count, monitor))
+                .detach(),
+        ),
+        ModuleKind::Workspaces => Box::new(
+            ActiveWorkspace::builder()
+                .launch((
+                    config.workspaces.clone(),
+                    config.animation.clone(),
+                    super::orientation(config.bar.edge),
+                    monitor,
+                ))
+                .detach(),
+        ),
+        ModuleKind::Datetime => {
+            Box::new(DateTime::builder().launch(config.datetime.clone()).detach())
+        }
+        ModuleKind::Taskbar => Box::new(
+            Taskbar::builder()
+                .launch((
+                    config.taskbar.clone(),
+                    config.window.clone(),
+                    super::orientation(config.bar.edge),
+                    monitor,
+                ))
+                .detach(),
+        ),
+        ModuleKind::Submap => Box::new(Submap::builder().launch(config.submap.clone()).detach()),
+        ModuleKind::Keyboard => Box::new(
+            KeyboardLayout::builder()
+                .launch(config.keyboard.clone())
+                .detach(),
+        ),
+    }
+}
+
+/// Whether the config section `kind` is built from differs between the two
+fn changed(kind: ModuleKind, old: &Config, new: &Config) -> bool {
+    match kind {
+        // the window menu has a button per workspace
+        ModuleKind::Window => {
+            old.window != new.window || old.workspaces.count != new.workspaces.count
+        }
+        // the workspaces turn with the bar
+        ModuleKind::Workspaces => {
+            old.workspaces != new.workspaces
+                || old.animation != new.animation
+                || old.bar.edge.is_vertical() != new.bar.edge.is_vertical()
+        }
+        ModuleKind::Datetime => old.datetime != new.datetime,
+        // titles go by the window rules
+        ModuleKind::Taskbar => {
+            old.taskbar != new.taskbar
+                || old.window.rules != new.window.rules
+                || old.bar.edge.is_vertical() != new.bar.edge.is_vertical()
+        }
+        ModuleKind::Submap => old.submap != new.submap,
+        ModuleKind::Keyboard => old.keyboard != new.keyboard,
+    }
+}
+
+/// The start, center or end box of the bar, along with its modules
+pub struct Slot {
+    container: gtk::Box,
+    modules: Vec<(ModuleKind, Box<dyn Module>)>,
+}
+
+impl Slot {
+    pub fn new(container: gtk::Box) -> Self {
+        Self {
+            container,
+            modules: Vec::new(),
+        }
+    }
+
+    pub fn set_spacing(&self, spacing: i32) {
+        self.container.set_spacing(spacing);
+    }
+
+    pub fn set_orientation(&self, orientation: gtk::Orientation) {
+        self.container.set_orientation(orientation);
+    }
+
+    fn kinds(&self) -> impl Iterator<Item = ModuleKind> + '_ {
+        self.modules.iter().map(|(kind, _)| *kind)
+    }
+
+    /// Replace all modules with new ones for `kinds`
+    pub fn fill(&mut self, kinds: &[ModuleKind], ctx: &ModuleContext) {
+        for (_, module) in self.modules.drain(..) {
+            self.container.remove(&module.widget());
+        }
+
+        for &kind in kinds {
+            let module = build(kind, ctx);
+            self.container.append(&module.widget());
+            self.modules.push((kind, module));
+        }
+    }
+
+    /// Move to `kinds` and the config in `ctx`, rebuilding as little as
+    /// possible: only modules whose section changed are replaced, unless the
+    /// slot holds different modules altogether.
+    pub fn update(&mut self, kinds: &[ModuleKind], old: &Config, ctx: &ModuleContext) {
+        if !self.kinds().eq(kinds.iter().copied()) {
+            self.fill(kinds, ctx);
+            return;
+        }
+
+        for (kind, module) in &mut self.modules {
+            if !changed(*kind, old, ctx.config) {
+                continue;
+            }
+
+            debug!("Rebuilding the {kind:?} module");
+
+            let new = build(*kind, ctx);
+            let old = mem::replace(module, new);
+
+            self.container
+                .insert_child_after(&module.widget(), Some(&old.widget()));
+            self.container.remove(&old.widget());
+        }
+    }
+}
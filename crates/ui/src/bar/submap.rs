@@ -0,0 +1,90 @@
+use gnyprland_config::SubmapConfig;
+use hyprland::event;
+
+use crate::prelude::*;
+
+/// The CSS classes of the module in `submap`, with anything GTK wouldn't take
+/// in a class name turned into `-`
+fn cname(submap: &str) -> [String; 3] {
+    let name = submap
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() || c == '_' {
+            true => c,
+            false => '-',
+        })
+        .collect::<String>();
+
+    [
+        String::from("element"),
+        String::from("submap"),
+        format!("submap-{name}"),
+    ]
+}
+
+#[derive(Clone, Debug)]
+pub enum Message {
+    /// The name of the active submap, empty for the default one
+    Submap(String),
+}
+
+pub struct Submap {
+    submap: String,
+    config: SubmapConfig,
+    _listener: ListenerHandle,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for Submap {
+    type Init = SubmapConfig;
+    type Input = Message;
+    type Output = ();
+
+    view! {
+        gtk::Box {
+            #[watch]
+            set_visible: !model.submap.is_empty(),
+            #[watch]
+            set_css_classes: &cname(&model.submap).each_ref().map(String::as_str),
+
+            gtk::Label {
+                #[watch]
+                set_label: model.config.label(&model.submap),
+                set_css_classes: &["text"],
+            },
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        // Hyprland only says when the submap changes, so this starts out in
+        // the default one
+        let (mut listener, handle) = EventListener::with_handle();
+        let model = Submap {
+            submap: String::new(),
+            config,
+            _listener: handle,
+        };
+
+        thread::spawn(move || {
+            listener.register::<event::Submap>(move |submap| {
+                debug!("Submap: {:?}", submap.name);
+                sender.input(Message::Submap(submap.name.clone()));
+            });
+
+            debug!("Watching for submap changes");
+            listener.listen().unwrap()
+        });
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, message: Self::Input, _: ComponentSender<Self>) {
+        let Message::Submap(submap) = message;
+        self.submap = submap;
+    }
+}
@@ -0,0 +1,453 @@
+use std::{collections::HashSet, sync::Arc};
+
+use gnyprland_config::{TaskbarConfig, TaskbarFilter, WindowConfig};
+use hyprland::{
+    command::{Client, Clients, Dispatch, Executor, Monitors, Workspaces},
+    error::CommandError,
+    event,
+};
+use relm4::{
+    factory::FactoryVecDeque,
+    gtk::{gdk, glib, pango::EllipsizeMode, Orientation},
+};
+
+use super::dispatch;
+use crate::{desktop, prelude::*};
+
+/// The address clients have for one an event gives, which lacks the `0x`
+fn address(event: &str) -> String {
+    format!("0x{event}")
+}
+
+/// Where the workspaces of a monitor are, to pick the windows to list
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Placement {
+    /// Every workspace on the monitor
+    workspaces: HashSet<i32>,
+    /// The workspace shown on the monitor
+    active: i32,
+    /// The special workspace open on top, or 0
+    special: i32,
+}
+
+impl Placement {
+    /// Whether `filter` lists windows on `workspace`
+    fn shows(&self, filter: TaskbarFilter, workspace: i32) -> bool {
+        match filter {
+            TaskbarFilter::All => true,
+            TaskbarFilter::Monitor => self.workspaces.contains(&workspace),
+            TaskbarFilter::Workspace => {
+                workspace == self.active || (self.special != 0 && workspace == self.special)
+            }
+        }
+    }
+}
+
+fn placement(monitor: &str) -> Result<Placement, CommandError> {
+    let (active, special) = Executor::command::<Monitors>()?
+        .into_iter()
+        .find(|m| m.name == monitor)
+        .map_or((0, 0), |m| (m.active_workspace.id, m.special_workspace.id));
+
+    Ok(Placement {
+        workspaces: Executor::command::<Workspaces>()?
+            .into_iter()
+            .filter(|w| w.monitor == monitor)
+            .map(|w| w.id)
+            .collect(),
+        active,
+        special,
+    })
+}
+
+/// A window in the taskbar
+#[derive(Debug)]
+pub struct Task {
+    address: String,
+    class: String,
+    /// The title as rewritten by the window rules
+    title: String,
+    workspace: i32,
+    focused: bool,
+    /// Whether the taskbar's filter lists it
+    visible: bool,
+    config: TaskbarConfig,
+}
+
+#[derive(Debug)]
+pub enum TaskOutput {
+    /// Put the window at address `from` in the place of the one at `onto`
+    Reorder { from: String, onto: String },
+}
+
+#[relm4::factory(pub)]
+impl FactoryComponent for Task {
+    type CommandOutput = ();
+    type Init = Task;
+    type Input = ();
+    type Output = TaskOutput;
+    type ParentWidget = gtk::Box;
+
+    view! {
+        gtk::Box {
+            set_orientation: Orientation::Horizontal,
+            set_spacing: 6,
+            #[watch]
+            set_css_classes: css!["task", "focused" if self.focused],
+            #[watch]
+            set_visible: self.visible,
+            #[watch]
+            set_tooltip_text: Some(&self.title),
+
+            // primary click focuses the window, middle click closes it
+            add_controller = gtk::GestureClick {
+                set_button: 0,
+                connect_released[address = self.address.clone()] => move |gesture, _, _, _| {
+                    match gesture.current_button() {
+                        gdk::BUTTON_PRIMARY => dispatch(Dispatch::FocusWindow(address.clone())),
+                        gdk::BUTTON_MIDDLE => dispatch(Dispatch::CloseWindow(address.clone())),
+                        _ => {}
+                    }
+                },
+            },
+
+            // dragging one window onto another puts it in that one's place
+            add_controller = gtk::DragSource {
+                set_actions: gdk::DragAction::MOVE,
+                connect_prepare[address = self.address.clone()] => move |_, _, _| {
+                    Some(gdk::ContentProvider::for_value(&address.to_value()))
+                },
+            },
+
+            add_controller = gtk::DropTarget::new(glib::Type::STRING, gdk::DragAction::MOVE) {
+                connect_drop[sender, address = self.address.clone()] => move |_, value, _, _| {
+                    let Ok(from) = value.get::<String>() else {
+                        return false;
+                    };
+
+                    let _ = sender.output(TaskOutput::Reorder {
+                        from,
+                        onto: address.clone(),
+                    });
+
+                    true
+                },
+            },
+
+            gtk::Image {
+                set_pixel_size: self.config.icon_size,
+                set_from_gicon: &desktop::icon(&self.class),
+            },
+
+            gtk::Label {
+                set_visible: self.config.titles,
+                set_max_width_chars: self.config.title_width,
+                set_ellipsize: EllipsizeMode::End,
+                #[watch]
+                set_label: &self.title,
+            },
+        }
+    }
+
+    fn init_model(task: Self::Init, _: &DynamicIndex, _: FactorySender<Self>) -> Self {
+        task
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Message {
+    Opened(Client),
+    Closed(String),
+    Moved {
+        address: String,
+        workspace: i32,
+    },
+    Retitled {
+        address: String,
+        title: String,
+    },
+    /// The window at this address got focus, or none did if it's empty
+    Focused(String),
+    Placement(Placement),
+    Reorder {
+        from: String,
+        onto: String,
+    },
+}
+
+pub struct Taskbar {
+    /// Connector name of the bar's monitor
+    monitor: String,
+    config: TaskbarConfig,
+    /// For the rules that rewrite titles
+    window: WindowConfig,
+    placement: Placement,
+
+    tasks: FactoryVecDeque<Task>,
+    _listener: ListenerHandle,
+}
+
+impl Taskbar {
+    fn task(&self, client: Client) -> Task {
+        Task {
+            title: self.window.title(&client.class, client.title),
+            visible: self
+                .placement
+                .shows(self.config.filter, client.workspace.id),
+            focused: client.focus_history_id == 0,
+            workspace: client.workspace.id,
+            class: client.class,
+            address: client.address,
+            config: self.config.clone(),
+        }
+    }
+
+    fn index(&self, address: &str) -> Option<usize> {
+        self.tasks.iter().position(|t| t.address == address)
+    }
+
+    /// Show only the windows the filter lists
+    fn filter(&mut self) {
+        let mut tasks = self.tasks.guard();
+
+        for index in 0..tasks.len() {
+            let Some(task) = tasks.get(index) else {
+                continue;
+            };
+
+            let visible = self.placement.shows(self.config.filter, task.workspace);
+
+            if task.visible != visible {
+                tasks.get_mut(index).unwrap().visible = visible;
+            }
+        }
+    }
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for Taskbar {
+    /// The config, the window rules for titles, which way the bar runs and
+    /// the monitor
+    type Init = (TaskbarConfig, WindowConfig, Orientation, String);
+    type Input = Message;
+    type Output = ();
+
+    view! {
+        gtk::Box {
+            set_css_classes: &["element", "taskbar"],
+
+            #[local_ref]
+            tasks_widget -> gtk::Box {
+                set_orientation: orientation,
+                set_spacing: 4,
+            },
+        }
+    }
+
+    fn init(
+        (config, window, orientation, monitor): Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let tasks = FactoryVecDeque::builder()
+            .launch(gtk::Box::default())
+            .forward(sender.input_sender(), |output| match output {
+                TaskOutput::Reorder { from, onto } => Message::Reorder { from, onto },
+            });
+
+        let (mut listener, handle) = EventListener::with_handle();
+        let mut model = Taskbar {
+            placement: placement(&monitor).unwrap_or_else(|e| {
+                warn!("Failed to look up the workspaces on {monitor}: {e}");
+                Placement::default()
+            }),
+            monitor,
+            config,
+            window,
+            tasks,
+            _listener: handle,
+        };
+
+        // grouped by workspace to begin with
+        let mut clients = Executor::command::<Clients>().unwrap_or_else(|e| {
+            warn!("Failed to list the windows: {e}");
+            Vec::new()
+        });
+        clients.sort_by_key(|c| c.workspace.id);
+
+        let initial = clients
+            .into_iter()
+            .map(|c| model.task(c))
+            .collect::<Vec<_>>();
+        let mut tasks = model.tasks.guard();
+
+        for task in initial {
+            tasks.push_back(task);
+        }
+
+        drop(tasks);
+
+        thread::spawn(clone!(
+            #[strong(rename_to = monitor)]
+            model.monitor,
+            move || {
+                // a window has to open before it can move or close, so this
+                // mustn't see those the other way around
+                listener.set_ordered(true);
+
+                let place = clone!(
+                    #[strong]
+                    sender,
+                    move || match placement(&monitor) {
+                        Ok(placement) => sender.input(Message::Placement(placement)),
+                        Err(e) => warn!("Failed to look up the workspaces on {monitor}: {e}"),
+                    }
+                );
+                let place = Arc::new(place);
+
+                macro_rules! place_on {
+                    ($($event:ty),*) => {
+                        $(listener.register::<$event>(clone!(
+                            #[strong]
+                            place,
+                            move |_| place()
+                        ));)*
+                    };
+                }
+
+                // all of these can change which windows the filter lists
+                place_on!(
+                    event::WorkspaceV2,
+                    event::FocusedMonitor,
+                    event::CreateWorkspace,
+                    event::DestroyWorkspace,
+                    event::MoveWorkspace,
+                    event::ActiveSpecial
+                );
+
+                listener.register::<event::OpenWindow>(clone!(
+                    #[strong]
+                    sender,
+                    move |window| {
+                        let address = address(&window.address);
+                        let clients = match Executor::command::<Clients>() {
+                            Ok(clients) => clients,
+                            Err(e) => {
+                                warn!("Failed to look up opened window {address}: {e}");
+                                return;
+                            }
+                        };
+
+                        match clients.into_iter().find(|c| c.address == address) {
+                            Some(client) => sender.input(Message::Opened(client)),
+                            None => debug!("Opened window {address} is already gone"),
+                        }
+                    }
+                ));
+                listener.register::<event::CloseWindow>(clone!(
+                    #[strong]
+                    sender,
+                    move |window| sender.input(Message::Closed(address(&window.address)))
+                ));
+                listener.register::<event::MoveWindowV2>(clone!(
+                    #[strong]
+                    sender,
+                    move |window| {
+                        sender.input(Message::Moved {
+                            address: address(&window.address),
+                            workspace: window.id,
+                        })
+                    }
+                ));
+                listener.register::<event::WindowTitleV2>(clone!(
+                    #[strong]
+                    sender,
+                    move |window| {
+                        sender.input(Message::Retitled {
+                            address: address(&window.address),
+                            title: window.title.clone(),
+                        })
+                    }
+                ));
+                listener.register::<event::ActiveWindowV2>(move |window| {
+                    let focused = match window.address.is_empty() {
+                        true => String::new(),
+                        false => address(&window.address),
+                    };
+
+                    sender.input(Message::Focused(focused));
+                });
+
+                debug!("Watching for window changes");
+                listener.listen().unwrap()
+            }
+        ));
+
+        let tasks_widget = model.tasks.widget();
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, message: Self::Input, _: ComponentSender<Self>) {
+        match message {
+            Message::Opened(client) => {
+                if self.index(&client.address).is_none() {
+                    let task = self.task(client);
+                    self.tasks.guard().push_back(task);
+                }
+            }
+            Message::Closed(address) => {
+                if let Some(index) = self.index(&address) {
+                    self.tasks.guard().remove(index);
+                }
+            }
+            Message::Moved { address, workspace } => {
+                if let Some(index) = self.index(&address) {
+                    let mut tasks = self.tasks.guard();
+                    let task = tasks.get_mut(index).unwrap();
+
+                    task.workspace = workspace;
+                    task.visible = self.placement.shows(self.config.filter, workspace);
+                }
+            }
+            Message::Retitled { address, title } => {
+                if let Some(index) = self.index(&address) {
+                    let mut tasks = self.tasks.guard();
+                    let task = tasks.get_mut(index).unwrap();
+
+                    task.title = self.window.title(&task.class, title);
+                }
+            }
+            Message::Focused(address) => {
+                let mut tasks = self.tasks.guard();
+
+                for index in 0..tasks.len() {
+                    let Some(task) = tasks.get(index) else {
+                        continue;
+                    };
+
+                    let focused = task.address == address;
+
+                    if task.focused != focused {
+                        tasks.get_mut(index).unwrap().focused = focused;
+                    }
+                }
+            }
+            Message::Placement(placement) => {
+                if self.placement != placement {
+                    trace!("Workspaces on {}: {placement:?}", self.monitor);
+                    self.placement = placement;
+                    self.filter();
+                }
+            }
+            Message::Reorder { from, onto } => {
+                let (Some(from), Some(onto)) = (self.index(&from), self.index(&onto)) else {
+                    return;
+                };
+
+                self.tasks.guard().move_to(from, onto);
+            }
+        }
+    }
+}
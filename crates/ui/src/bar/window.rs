@@ -1,121 +1,446 @@
-use std::{collections::HashMap, sync::LazyLock};
+use std::sync::Arc;
 
+use gnyprland_config::WindowConfig;
 use hyprland::{
-    command::{self, Executor},
-    event::{self, ActiveWindowData},
+    command::{self, Client, Dispatch, Executor},
+    error::CommandError,
+    event,
+};
+use relm4::gtk::{
+    gdk, glib::Propagation, pango::EllipsizeMode, EventControllerScroll,
+    EventControllerScrollFlags, GestureClick, Orientation,
 };
-use relm4::gtk::{pango::EllipsizeMode, Orientation};
 
-use crate::prelude::*;
+use super::dispatch;
+use crate::{desktop, prelude::*};
 
-type ClassMap = HashMap<&'static str, fn(String) -> String>;
+/// How many workspace buttons go in a row of the menu
+const MENU_COLUMNS: usize = 5;
 
-static TITLE_OVERRIDES: LazyLock<ClassMap> = LazyLock::new(|| {
-    hash_map! {
-        "kitty" => (|_| String::from("Terminal")) as fn(String) -> String,
-        "code" => |title| title.replace(" - Visual Studio Code", ""),
-        "firefox" => |title| title.replace("Mozilla Firefox", "Firefox"),
-        "" => |_| String::from("Desktop")
-    }
-});
+/// The most recently focused window on `monitor`'s active workspace, which is
+/// the active window whenever the monitor has focus, along with the windows
+/// of its group in tab order. Neither when Hyprland can't be asked.
+fn active_on(monitor: &str) -> (Option<Client>, Vec<Client>) {
+    let lookup = || -> Result<_, CommandError> {
+        let workspace = Executor::command::<command::Monitors>()?
+            .into_iter()
+            .find(|m| m.name == monitor)
+            .map(|m| m.active_workspace.id);
 
-static CLASS_OVERRIDES: LazyLock<ClassMap> = LazyLock::new(|| {
-    hash_map! {
-        "dev.zed.Zed" => (|_| String::from("zed")) as fn(String) -> String,
-        "" => |_| String::from("desktop")
-    }
-});
+        Ok((workspace, Executor::command::<command::Clients>()?))
+    };
+
+    let (workspace, clients) = match lookup() {
+        Ok(found) => found,
+        Err(e) => {
+            warn!("Failed to look up the active window on {monitor}: {e}");
+            return (None, Vec::new());
+        }
+    };
 
-fn title_override(class: &str, title: String) -> String {
-    TITLE_OVERRIDES.get(class).copied().unwrap_or(identity)(title)
+    let active = clients
+        .iter()
+        .filter(|c| Some(c.workspace.id) == workspace)
+        .min_by_key(|c| c.focus_history_id)
+        .cloned();
+
+    let group = match &active {
+        Some(window) if window.grouped.len() > 1 => window
+            .grouped
+            .iter()
+            .filter_map(|address| clients.iter().find(|c| &c.address == address))
+            .cloned()
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    (active, group)
 }
 
-fn class_override(class: String) -> String {
-    CLASS_OVERRIDES
-        .get(class.as_str())
-        .copied()
-        .unwrap_or(identity)(class)
+/// Something to do to the active window from its menu
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Close,
+    ToggleFloating,
+    Fullscreen,
+    Pin,
+    /// Move to the numbered workspace
+    MoveTo(usize),
+    MoveToSpecial,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug)]
 pub enum Message {
-    Update(ActiveWindowData),
+    /// The active window and the windows of its group
+    Update(Option<Client>, Vec<Client>),
+    Menu,
+    Action(Action),
+    /// Focus the next window on the monitor's workspace, or the previous one
+    Cycle {
+        prev: bool,
+    },
+    /// Switch the active window's group to the window at this index
+    Tab(usize),
 }
 
 pub struct ActiveWindow {
-    active: ActiveWindowData,
+    /// Connector name of the bar's monitor
+    monitor: String,
+    active: Option<Client>,
+    /// The windows of the active window's group, if it's in one
+    group: Vec<Client>,
+    config: WindowConfig,
+
+    menu: gtk::Popover,
+    tabs: gtk::Box,
+    _listener: ListenerHandle,
+}
+
+impl ActiveWindow {
+    fn title(&self) -> String {
+        let (class, title) = match &self.active {
+            Some(window) => (window.class.as_str(), window.title.clone()),
+            None => ("", String::new()),
+        };
+
+        self.config.title(class, title)
+    }
+
+    /// The class as rewritten by the rules, or else the app's name
+    fn class(&self) -> String {
+        let (class, title) = match &self.active {
+            Some(window) => (window.class.as_str(), window.title.as_str()),
+            None => ("", ""),
+        };
+
+        self.config
+            .class(class, title)
+            .or_else(|| match self.config.desktop_names {
+                true => desktop::name(class),
+                false => None,
+            })
+            .unwrap_or_else(|| class.to_string())
+    }
+
+    fn act(&self, action: Action) {
+        let Some(window) = &self.active else {
+            return;
+        };
+
+        debug!("{action:?} on {} ({})", window.class, window.address);
+        let address = window.address.clone();
+
+        match action {
+            Action::Close => dispatch(Dispatch::CloseWindow(address)),
+            Action::ToggleFloating => dispatch(Dispatch::ToggleFloating(address)),
+            Action::Pin => dispatch(Dispatch::Pin(address)),
+            // fullscreen only takes the focused window
+            Action::Fullscreen => {
+                dispatch(Dispatch::FocusWindow(address));
+                dispatch(Dispatch::Fullscreen);
+            }
+            Action::MoveTo(workspace) => dispatch(Dispatch::MoveToWorkspaceSilent {
+                workspace: workspace.to_string(),
+                window: address,
+            }),
+            Action::MoveToSpecial => dispatch(Dispatch::MoveToWorkspaceSilent {
+                workspace: String::from("special"),
+                window: address,
+            }),
+        }
+    }
+
+    /// Show a tab for each window in the active window's group
+    fn update_tabs(&self, sender: &ComponentSender<Self>) {
+        while let Some(child) = self.tabs.first_child() {
+            self.tabs.remove(&child);
+        }
+
+        let active = self.active.as_ref().map(|w| w.address.as_str());
+
+        for (index, window) in self.group.iter().enumerate() {
+            let tab = gtk::Box::new(Orientation::Horizontal, 4);
+
+            let icon = gtk::Image::from_gicon(&desktop::icon(&window.class));
+            icon.set_pixel_size(16);
+            tab.append(&icon);
+
+            let title = self.config.title(&window.class, window.title.clone());
+            let label = gtk::Label::new(Some(&title));
+            label.set_max_width_chars(8);
+            label.set_ellipsize(EllipsizeMode::End);
+            tab.append(&label);
+
+            let button = gtk::Button::new();
+            button.set_child(Some(&tab));
+            button.set_tooltip_text(Some(&title));
+            button.set_css_classes(css![
+                "flat",
+                "tab",
+                "active" if Some(window.address.as_str()) == active
+            ]);
+            button.connect_clicked(clone!(
+                #[strong]
+                sender,
+                move |_| sender.input(Message::Tab(index))
+            ));
+
+            self.tabs.append(&button);
+        }
+
+        self.tabs
+            .set_visible(self.config.group_tabs && !self.group.is_empty());
+    }
+}
+
+/// The window actions popover, with a button per workspace to move to
+fn menu(workspaces: usize, sender: &ComponentSender<ActiveWindow>) -> gtk::Popover {
+    let button = |label: &str, action: Action| {
+        let button = gtk::Button::with_label(label);
+        button.set_css_classes(css!["flat"]);
+        button.connect_clicked(clone!(
+            #[strong]
+            sender,
+            move |_| sender.input(Message::Action(action))
+        ));
+        button
+    };
+
+    let list = gtk::Box::new(Orientation::Vertical, 0);
+    list.append(&button("Close", Action::Close));
+    list.append(&button("Toggle floating", Action::ToggleFloating));
+    list.append(&button("Toggle fullscreen", Action::Fullscreen));
+    list.append(&button("Toggle pin", Action::Pin));
+
+    let label = gtk::Label::new(Some("Move to workspace"));
+    label.set_css_classes(css!["text-sub"]);
+    list.append(&label);
+
+    let grid = gtk::Grid::new();
+    grid.set_css_classes(css!["workspaces"]);
+
+    for n in 1..=workspaces {
+        let (column, row) = ((n - 1) % MENU_COLUMNS, (n - 1) / MENU_COLUMNS);
+        grid.attach(
+            &button(&n.to_string(), Action::MoveTo(n)),
+            column as i32,
+            row as i32,
+            1,
+            1,
+        );
+    }
+
+    list.append(&grid);
+    list.append(&button("Move to special workspace", Action::MoveToSpecial));
+
+    let menu = gtk::Popover::new();
+    menu.set_css_classes(css!["window-menu"]);
+    menu.set_child(Some(&list));
+    menu
 }
 
 #[relm4::component(pub)]
 impl SimpleComponent for ActiveWindow {
-    type Init = ();
+    /// The config, how many workspaces there are to move windows to and the
+    /// monitor
+    type Init = (WindowConfig, usize, String);
     type Input = Message;
     type Output = ();
 
     view! {
         gtk::Box {
-            set_orientation: Orientation::Vertical,
+            set_orientation: Orientation::Horizontal,
             set_css_classes: css!["element", "active-window"],
 
-            gtk::Label {
+            gtk::Image {
+                set_css_classes: &["icon"],
+                set_visible: model.config.icon,
+                set_pixel_size: model.config.icon_size,
                 #[watch]
-                set_label: &title_override(&model.active.class, model.active.title.clone()),
-                set_css_classes: &["text"],
-                set_max_width_chars: 10,
-                set_ellipsize: EllipsizeMode::End
+                set_from_gicon: &desktop::icon(model.active.as_ref().map_or("", |w| &w.class)),
             },
 
-            gtk::Label {
-                #[watch]
-                set_label: &class_override(model.active.class.clone()),
-                set_css_classes: &["text-sub"],
-                set_max_width_chars: 10,
-                set_ellipsize: EllipsizeMode::End,
-            }
+            gtk::Box {
+                set_orientation: Orientation::Vertical,
+                set_hexpand: true,
+
+                gtk::Label {
+                    #[watch]
+                    set_label: &model.title(),
+                    set_css_classes: &["text"],
+                    set_max_width_chars: 10,
+                    set_ellipsize: EllipsizeMode::End
+                },
+
+                gtk::Label {
+                    #[watch]
+                    set_label: &model.class(),
+                    set_css_classes: &["text-sub"],
+                    set_max_width_chars: 10,
+                    set_ellipsize: EllipsizeMode::End,
+                }
+            },
+
+            #[local_ref]
+            tabs -> gtk::Box {
+                set_css_classes: &["group-tabs"],
+                set_spacing: 2,
+            },
         }
     }
 
     fn init(
-        _init: Self::Init,
-        _root: Self::Root,
+        (config, workspaces, monitor): Self::Init,
+        root: Self::Root,
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
+        // primary click opens the menu, middle click closes the window
+        let click = GestureClick::builder().button(0).build();
+        click.connect_released(clone!(
+            #[strong]
+            sender,
+            move |gesture, _, _, _| match gesture.current_button() {
+                gdk::BUTTON_PRIMARY => sender.input(Message::Menu),
+                gdk::BUTTON_MIDDLE => sender.input(Message::Action(Action::Close)),
+                _ => {}
+            }
+        ));
+        root.add_controller(click);
+
+        let scroll = EventControllerScroll::new(
+            EventControllerScrollFlags::VERTICAL | EventControllerScrollFlags::DISCRETE,
+        );
+        scroll.connect_scroll(clone!(
+            #[strong]
+            sender,
+            move |_, _, dy| {
+                sender.input(Message::Cycle { prev: dy < 0.0 });
+                Propagation::Stop
+            }
+        ));
+        root.add_controller(scroll);
+
+        let menu = menu(workspaces, &sender);
+        menu.set_parent(&root);
+
+        let (active, group) = active_on(&monitor);
+        let (mut listener, handle) = EventListener::with_handle();
+        let model = ActiveWindow {
+            monitor: monitor.clone(),
+            active,
+            group,
+            config,
+            menu,
+            tabs: gtk::Box::new(Orientation::Horizontal, 0),
+            _listener: handle,
+        };
+
+        model.update_tabs(&sender);
+
         thread::spawn(move || {
-            let mut listener = EventListener::new();
+            // each update asks Hyprland afresh, and an older answer mustn't
+            // land after a newer one
+            listener.set_ordered(true);
 
-            listener.register::<event::ActiveWindow>(move |window| {
-                trace!("Active window event: {window:?}");
+            let update = move || {
+                let (window, group) = active_on(&monitor);
 
                 trace!(
-                    "Active window changed: {} (class: {})",
-                    window.title,
-                    window.class
+                    "Active window on {monitor}: {:?}",
+                    window.as_ref().map(|w| (&w.title, &w.class))
                 );
 
-                sender.input(Message::Update(window.clone()));
-            });
+                sender.input(Message::Update(window, group));
+            };
+            let update = Arc::new(update);
+
+            listener.register::<event::ActiveWindow>(clone!(
+                #[strong]
+                update,
+                move |window| {
+                    trace!("Active window event: {window:?}");
+                    update();
+                }
+            ));
+
+            // another window becomes the active one on this monitor when it
+            // switches workspaces or its window goes away
+            listener.register::<event::Workspace>(clone!(
+                #[strong]
+                update,
+                move |_| update()
+            ));
+            listener.register::<event::FocusedMonitor>(clone!(
+                #[strong]
+                update,
+                move |_| update()
+            ));
+            listener.register::<event::CloseWindow>(clone!(
+                #[strong]
+                update,
+                move |_| update()
+            ));
+            listener.register::<event::MoveWindow>(clone!(
+                #[strong]
+                update,
+                move |_| update()
+            ));
+
+            // the group tabs change with the group
+            listener.register::<event::ToggleGroup>(clone!(
+                #[strong]
+                update,
+                move |_| update()
+            ));
+            listener.register::<event::MoveIntoGroup>(clone!(
+                #[strong]
+                update,
+                move |_| update()
+            ));
+            listener.register::<event::MoveOutOfGroup>(move |_| update());
 
             debug!("Watching for active window changes");
             listener.listen().unwrap()
         });
 
-        let current_active = Executor::command::<command::ActiveWindow>().unwrap();
-
-        let model = ActiveWindow {
-            active: ActiveWindowData {
-                class: current_active.class,
-                title: current_active.title,
-            },
-        };
-
+        let tabs = &model.tabs;
         let widgets = view_output!();
 
         ComponentParts { model, widgets }
     }
 
-    fn update(&mut self, message: Self::Input, _: ComponentSender<Self>) {
+    fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
         match message {
-            Message::Update(window) => self.active = window,
+            Message::Update(window, group) => {
+                self.active = window;
+                self.group = group;
+                self.update_tabs(&sender);
+            }
+            Message::Menu => {
+                if self.active.is_some() {
+                    self.menu.popup();
+                }
+            }
+            Message::Action(action) => {
+                self.menu.popdown();
+                self.act(action);
+            }
+            Message::Cycle { prev } => {
+                // cycling goes through the focused monitor's workspace
+                dispatch(Dispatch::FocusMonitor(self.monitor.clone()));
+                dispatch(Dispatch::CycleNext { prev });
+            }
+            // switching goes through the group of the focused window
+            Message::Tab(index) => {
+                if let Some(window) = &self.active {
+                    dispatch(Dispatch::FocusWindow(window.address.clone()));
+                    dispatch(Dispatch::ChangeGroupActive(index));
+                }
+            }
         }
     }
+
+    fn shutdown(&mut self, _: &mut Self::Widgets, _: relm4::Sender<Self::Output>) {
+        self.menu.unparent();
+    }
 }
@@ -1,50 +1,552 @@
 mod indicator;
 mod slider;
 
+use std::{collections::HashSet, sync::Arc};
+
+use gnyprland_config::{AnimationConfig, WorkspacesConfig, WorkspacesMode};
+use hyprland::{
+    command::{
+        ActiveWorkspace as FocusedWorkspace, Client, Clients, Dispatch, Executor, Monitors,
+        Workspace, Workspaces,
+    },
+    error::CommandError,
+    event,
+};
 use indicator::OpenIndicator;
+use relm4::gtk::{
+    glib::Propagation, EventControllerScroll, EventControllerScrollFlags, GestureDrag, Orientation,
+};
 use slider::ActiveSlider;
 
+use super::dispatch;
 use crate::prelude::*;
 
-#[allow(dead_code)]
+/// A window on a workspace, as the indicator shows it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Window {
+    pub address: String,
+    pub class: String,
+    pub title: String,
+}
+
+impl From<Client> for Window {
+    fn from(client: Client) -> Self {
+        Self {
+            address: client.address,
+            class: client.class,
+            title: client.title,
+        }
+    }
+}
+
+/// A workspace with a place in the indicator
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Slot {
+    pub id: i32,
+    pub name: String,
+    pub windows: usize,
+    /// Whether a window on it asked for attention since it was last visited
+    pub urgent: bool,
+    /// The windows on it, only filled in when the indicator shows them
+    pub clients: Vec<Window>,
+}
+
+impl Slot {
+    fn empty(id: i32) -> Self {
+        Self {
+            id,
+            name: id.to_string(),
+            windows: 0,
+            urgent: false,
+            clients: Vec::new(),
+        }
+    }
+
+    /// Whether the workspace goes by a name rather than its number
+    pub fn is_named(&self) -> bool {
+        self.name != self.id.to_string()
+    }
+
+    /// How dispatchers refer to the workspace
+    fn selector(&self) -> String {
+        match self.id > 0 {
+            true => self.id.to_string(),
+            false => format!("name:{}", self.name),
+        }
+    }
+}
+
+impl From<Workspace> for Slot {
+    fn from(workspace: Workspace) -> Self {
+        Self {
+            id: workspace.id,
+            name: workspace.name,
+            windows: workspace.windows,
+            urgent: false,
+            clients: Vec::new(),
+        }
+    }
+}
+
+/// The workspaces of a monitor, as the widgets show them
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Snapshot {
+    /// Regular workspaces by number, then named ones in the order they were
+    /// made
+    slots: Vec<Slot>,
+    /// Index of the workspace shown on the monitor
+    active: Option<usize>,
+    /// Special workspaces, and whether each is open on the monitor
+    special: Vec<(Slot, bool)>,
+}
+
+impl Snapshot {
+    /// The index of the workspace to scroll to from the one at `from`, going
+    /// by the `wrap` and `skip_empty` settings of `config`
+    fn scroll(&self, from: usize, forward: bool, config: &WorkspacesConfig) -> Option<usize> {
+        let count = self.slots.len();
+        let mut index = from;
+
+        for _ in 1..count {
+            index = match (forward, config.wrap) {
+                (true, _) if index + 1 < count => index + 1,
+                (false, _) if index > 0 => index - 1,
+                (true, true) => 0,
+                (false, true) => count - 1,
+                (_, false) => return None,
+            };
+
+            if !config.skip_empty || self.slots[index].windows > 0 {
+                return Some(index);
+            }
+        }
+
+        None
+    }
+}
+
+/// Take a snapshot of the workspaces on `monitor`, or of the focused one if
+/// Hyprland doesn't know the monitor
+fn snapshot(config: &WorkspacesConfig, monitor: &str) -> Result<Snapshot, CommandError> {
+    let (active, special) = match Executor::command::<Monitors>()?
+        .into_iter()
+        .find(|m| m.name == monitor)
+    {
+        Some(m) => (m.active_workspace.id, m.special_workspace.id),
+        None => (Executor::command::<FocusedWorkspace>()?.id, 0),
+    };
+
+    let mut snapshot = Snapshot {
+        slots: match config.mode {
+            WorkspacesMode::Static => (1..=config.count as i32).map(Slot::empty).collect(),
+            WorkspacesMode::Existing => Vec::new(),
+        },
+        ..Snapshot::default()
+    };
+
+    for workspace in Executor::command::<Workspaces>()?
+        .into_iter()
+        .filter(|w| w.monitor == monitor)
+    {
+        if workspace.is_special() {
+            if config.special {
+                let open = workspace.id == special;
+                snapshot.special.push((workspace.into(), open));
+            }
+
+            continue;
+        }
+
+        match snapshot.slots.iter_mut().find(|s| s.id == workspace.id) {
+            Some(slot) => *slot = workspace.into(),
+            None => snapshot.slots.push(workspace.into()),
+        }
+    }
+
+    // named workspaces count down from -1337
+    snapshot.slots.sort_by_key(|s| (s.id < 0, s.id.abs()));
+    snapshot.special.sort_by_key(|(s, _)| s.id);
+
+    if config.icons {
+        for client in Executor::command::<Clients>()? {
+            let id = client.workspace.id;
+
+            if let Some(slot) = snapshot.slots.iter_mut().find(|s| s.id == id) {
+                slot.clients.push(client.into());
+            }
+        }
+    }
+    snapshot.active = snapshot.slots.iter().position(|s| s.id == active);
+
+    Ok(snapshot)
+}
+
+/// The id of the workspace holding the window at `address`, as the `urgent`
+/// event gives it
+fn workspace_of(address: &str) -> Result<Option<i32>, CommandError> {
+    Ok(Executor::command::<Clients>()?
+        .into_iter()
+        .find(|c| c.address.trim_start_matches("0x") == address)
+        .map(|c| c.workspace.id))
+}
+
+/// The label of a special workspace, without the `special:` prefix
+fn special_label(slot: &Slot) -> &str {
+    slot.name.strip_prefix("special:").unwrap_or(&slot.name)
+}
+
+#[derive(Clone, Debug)]
+pub enum Message {
+    Update(Snapshot),
+    /// A window on the workspace with this id asked for attention
+    Urgent(i32),
+    /// Go to the workspace under the pointer at `(x, y)` on the slider
+    Pointer(f64, f64),
+    /// Go to the workspace at this index
+    Go(usize),
+    /// Focus the window at this address
+    Focus(String),
+    Scroll {
+        forward: bool,
+    },
+}
+
 pub struct ActiveWorkspace {
+    /// Connector name of the bar's monitor
+    monitor: String,
+    config: WorkspacesConfig,
+    orientation: Orientation,
+    snapshot: Snapshot,
+    /// The workspace last switched to, until the next snapshot shows it
+    target: Option<usize>,
+    /// Ids of the workspaces with urgent windows that haven't been visited
+    urgent: HashSet<i32>,
+
     slider: Controller<ActiveSlider>,
     indicator: Controller<OpenIndicator>,
+    special: gtk::Box,
+    _listener: ListenerHandle,
 }
 
 #[relm4::component(pub)]
 impl SimpleComponent for ActiveWorkspace {
-    type Init = ();
-    type Input = ();
+    /// The config, how the slider animates, which way the bar runs and the
+    /// monitor
+    type Init = (WorkspacesConfig, AnimationConfig, Orientation, String);
+    type Input = Message;
     type Output = ();
 
     view! {
         gtk::Box {
-            set_orientation: relm4::gtk::Orientation::Vertical,
+            set_orientation: orientation,
             set_css_classes: &["element", "active-workspace"],
 
-            #[local_ref]
-            slider_widget -> root!(ActiveSlider),
+            // the slider goes alongside the indicator
+            #[name = "track"]
+            gtk::Box {
+                set_orientation: match orientation {
+                    Orientation::Vertical => Orientation::Horizontal,
+                    _ => Orientation::Vertical,
+                },
+                set_cursor_from_name: Some("pointer"),
 
-            #[local_ref]
-            indicator_widget -> root!(OpenIndicator),
+                #[local_ref]
+                slider_widget -> root!(ActiveSlider) {
+                    // the icons show which workspace is active themselves
+                    set_visible: !config.icons,
+                },
+
+                #[local_ref]
+                indicator_widget -> root!(OpenIndicator),
+            },
+
+            #[name = "special"]
+            gtk::Box {
+                set_orientation: orientation,
+                set_css_classes: &["special-workspaces"],
+                set_spacing: 4,
+            },
         }
     }
 
     fn init(
-        _: Self::Init,
+        (config, animation, orientation, monitor): Self::Init,
         root: Self::Root,
-        _sender: relm4::ComponentSender<Self>,
+        sender: relm4::ComponentSender<Self>,
     ) -> relm4::ComponentParts<Self> {
-        let slider = ActiveSlider::builder().launch(()).detach();
-        let indicator = OpenIndicator::builder().launch(()).detach();
+        let initial = snapshot(&config, &monitor).unwrap_or_else(|e| {
+            warn!("Failed to look up the workspaces on {monitor}: {e}");
+            Snapshot::default()
+        });
+
+        let slider = ActiveSlider::builder()
+            .launch((
+                initial.active.unwrap_or(0),
+                initial.slots.len(),
+                orientation,
+                animation,
+            ))
+            .detach();
+        let indicator = OpenIndicator::builder()
+            .launch((
+                initial.slots.clone(),
+                initial.active,
+                orientation,
+                config.icons.then_some(config.icon_size),
+            ))
+            .forward(sender.input_sender(), |output| match output {
+                indicator::Output::Go(index) => Message::Go(index),
+                indicator::Output::Focus(address) => Message::Focus(address),
+            });
+
+        let (mut listener, handle) = EventListener::with_handle();
+
+        thread::spawn(clone!(
+            #[strong]
+            config,
+            #[strong]
+            monitor,
+            #[strong]
+            sender,
+            move || {
+                let update = move || match snapshot(&config, &monitor) {
+                    Ok(snapshot) => {
+                        trace!("Workspaces on {monitor}: {snapshot:?}");
+                        sender.input(Message::Update(snapshot));
+                    }
+                    Err(e) => warn!("Failed to look up the workspaces on {monitor}: {e}"),
+                };
+                let update = Arc::new(update);
+
+                macro_rules! refresh_on {
+                    ($($event:ty),*) => {
+                        $(listener.register::<$event>(clone!(
+                            #[strong]
+                            update,
+                            move |_| update()
+                        ));)*
+                    };
+                }
+
+                // all of these can change which workspaces there are, where
+                // they are and which have windows. Focusing another monitor
+                // can also switch workspaces without a workspace event.
+                refresh_on!(
+                    event::WorkspaceV2,
+                    event::FocusedMonitor,
+                    event::CreateWorkspace,
+                    event::DestroyWorkspace,
+                    event::MoveWorkspace,
+                    event::RenameWorkspace,
+                    event::ActiveSpecial,
+                    event::OpenWindow,
+                    event::CloseWindow,
+                    event::MoveWindow
+                );
+
+                // the icons go with the titles of the windows
+                if config.icons {
+                    refresh_on!(event::WindowTitle);
+                }
+
+                listener.register::<event::Urgent>(clone!(
+                    #[strong]
+                    sender,
+                    move |urgent| match workspace_of(&urgent.address) {
+                        Ok(Some(id)) => sender.input(Message::Urgent(id)),
+                        Ok(None) => debug!("No workspace has urgent window {}", urgent.address),
+                        Err(e) => warn!("Failed to look up urgent window {}: {e}", urgent.address),
+                    }
+                ));
+
+                debug!("Watching for workspace changes");
+                listener.listen().unwrap()
+            }
+        ));
 
         let slider_widget = slider.widget();
         let indicator_widget = indicator.widget();
 
         let widgets = view_output!();
-        let model = ActiveWorkspace { slider, indicator };
+
+        // pressing on a workspace switches to it, and dragging keeps
+        // switching to whichever is under the pointer. The icons go to their
+        // workspace when clicked instead.
+        if !config.icons {
+            let drag = GestureDrag::new();
+            drag.connect_drag_begin(clone!(
+                #[strong]
+                sender,
+                move |_, x, y| sender.input(Message::Pointer(x, y))
+            ));
+            drag.connect_drag_update(clone!(
+                #[strong]
+                sender,
+                move |drag, dx, dy| {
+                    if let Some((x, y)) = drag.start_point() {
+                        sender.input(Message::Pointer(x + dx, y + dy));
+                    }
+                }
+            ));
+            widgets.track.add_controller(drag);
+        }
+
+        let scroll = EventControllerScroll::new(
+            EventControllerScrollFlags::VERTICAL | EventControllerScrollFlags::DISCRETE,
+        );
+        scroll.connect_scroll(move |_, _, dy| {
+            sender.input(Message::Scroll { forward: dy > 0.0 });
+            Propagation::Stop
+        });
+        root.add_controller(scroll);
+
+        let model = ActiveWorkspace {
+            monitor,
+            config,
+            orientation,
+            snapshot: initial,
+            target: None,
+            urgent: HashSet::new(),
+            slider,
+            indicator,
+            special: widgets.special.clone(),
+            _listener: handle,
+        };
+
+        model.update_special(&model.snapshot);
 
         ComponentParts { model, widgets }
     }
+
+    fn update(&mut self, message: Self::Input, _: ComponentSender<Self>) {
+        match message {
+            Message::Update(snapshot) => self.apply(snapshot),
+            Message::Urgent(id) => {
+                let mut snapshot = self.snapshot.clone();
+                self.urgent.insert(id);
+                self.flag_urgent(&mut snapshot);
+
+                if self.snapshot.slots != snapshot.slots {
+                    self.indicator
+                        .emit(indicator::Message::Slots(snapshot.slots.clone()));
+                    self.snapshot = snapshot;
+                }
+            }
+            Message::Pointer(x, y) => {
+                let index = slider::index_at(
+                    self.slider.widget(),
+                    self.orientation,
+                    (x, y),
+                    self.snapshot.slots.len(),
+                );
+
+                if let Some(index) = index {
+                    self.go(index);
+                }
+            }
+            Message::Go(index) => self.go(index),
+            Message::Focus(address) => {
+                debug!("Focusing window {address}");
+                dispatch(Dispatch::FocusWindow(address));
+            }
+            Message::Scroll { forward } => {
+                let Some(from) = self.target.or(self.snapshot.active) else {
+                    return;
+                };
+
+                if let Some(index) = self.snapshot.scroll(from, forward, &self.config) {
+                    self.go(index);
+                }
+            }
+        }
+    }
+}
+
+impl ActiveWorkspace {
+    /// Mark the workspaces in `snapshot` with urgent windows, forgetting
+    /// about the one it shows and any that are gone
+    fn flag_urgent(&mut self, snapshot: &mut Snapshot) {
+        if let Some(active) = snapshot.active {
+            self.urgent.remove(&snapshot.slots[active].id);
+        }
+
+        self.urgent
+            .retain(|id| snapshot.slots.iter().any(|s| s.id == *id));
+
+        for slot in &mut snapshot.slots {
+            slot.urgent = self.urgent.contains(&slot.id);
+        }
+    }
+
+    /// Pass the changes in `snapshot` on to the widgets
+    fn apply(&mut self, mut snapshot: Snapshot) {
+        self.flag_urgent(&mut snapshot);
+        let old = &self.snapshot;
+
+        if let Some(active) = snapshot.active {
+            if old.active != snapshot.active || old.slots.len() != snapshot.slots.len() {
+                self.slider.emit(slider::Message::Move {
+                    active,
+                    count: snapshot.slots.len(),
+                });
+            }
+        }
+
+        if old.active != snapshot.active {
+            self.indicator
+                .emit(indicator::Message::Active(snapshot.active));
+        }
+
+        if old.slots != snapshot.slots {
+            self.indicator
+                .emit(indicator::Message::Slots(snapshot.slots.clone()));
+        }
+
+        if old.special != snapshot.special {
+            self.update_special(&snapshot);
+        }
+
+        self.snapshot = snapshot;
+        self.target = None;
+    }
+
+    /// Switch this monitor to the workspace at `index`, unless it's already
+    /// there or on its way
+    fn go(&mut self, index: usize) {
+        if Some(index) == self.snapshot.active || Some(index) == self.target {
+            return;
+        }
+
+        let Some(slot) = self.snapshot.slots.get(index) else {
+            return;
+        };
+
+        debug!("Switching {} to workspace {}", self.monitor, slot.name);
+        self.target = Some(index);
+
+        // new workspaces open on the focused monitor
+        dispatch(Dispatch::FocusMonitor(self.monitor.clone()));
+        dispatch(Dispatch::Workspace(slot.selector()));
+    }
+
+    /// Show a label for each special workspace in `snapshot`
+    fn update_special(&self, snapshot: &Snapshot) {
+        while let Some(child) = self.special.first_child() {
+            self.special.remove(&child);
+        }
+
+        for (slot, open) in &snapshot.special {
+            let label = gtk::Label::new(Some(special_label(slot)));
+            label.set_tooltip_text(Some(&slot.name));
+            label.set_css_classes(css![
+                "special-workspace",
+                "open" if *open,
+                "with-windows" if slot.windows > 0
+            ]);
+
+            self.special.append(&label);
+        }
+
+        self.special.set_visible(!snapshot.special.is_empty());
+    }
 }
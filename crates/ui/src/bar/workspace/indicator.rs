@@ -1,81 +1,221 @@
-use hyprland::{
-    command::{Executor, Workspaces},
-    event,
+use relm4::gtk::{
+    gdk, glib, pango::EllipsizeMode, EventControllerMotion, GestureClick, Orientation, PositionType,
 };
 
-use crate::prelude::*;
+use super::Slot;
+use crate::{desktop, prelude::*};
 
-fn calculate() -> u16 {
-    Executor::command::<Workspaces>()
-        .unwrap()
-        .into_iter()
-        .take(10)
-        .map(|n| (str::parse(&n.name).unwrap_or(1) - 1, n.windows))
-        .filter(|(_, n)| *n > 0)
-        .fold(0u16, |mask, (i, _)| mask | (1 << i))
+/// How long the window list stays up after the pointer leaves, so it can
+/// reach the list from the workspace
+const PREVIEW_DELAY: Duration = Duration::from_millis(200);
+
+fn cname(slot: &Slot, active: bool, icons: bool) -> Vec<&'static str> {
+    css![
+        "workspace-indicator" if !icons,
+        "workspace-windows" if icons,
+        "active" if active,
+        "with-windows" if slot.windows > 0,
+        "named" if slot.is_named(),
+        "urgent" if slot.urgent
+    ]
+    .to_vec()
+}
+
+#[derive(Clone, Debug)]
+pub enum Message {
+    Slots(Vec<Slot>),
+    /// Index of the workspace shown on the monitor
+    Active(Option<usize>),
+    /// The pointer went over the workspace at this index or its window list,
+    /// or left them
+    Hover(Option<usize>),
+    /// Hide the window list, unless the pointer came back since
+    Conceal(usize),
 }
 
-fn cname(mask: u16, n: usize) -> Vec<&'static str> {
-    css!["workspace-indicator", "with-windows" if mask & (1 << n) > 0].to_vec()
+#[derive(Clone, Debug)]
+pub enum Output {
+    /// Go to the workspace at this index
+    Go(usize),
+    /// Focus the window at this address
+    Focus(String),
 }
 
+/// A dot for every workspace, filled in when it has windows and highlighted
+/// when one of them is urgent. With icons, the windows on each workspace
+/// instead.
 pub struct OpenIndicator {
-    mask: u16,
+    slots: Vec<Slot>,
+    active: Option<usize>,
+    /// Size of the window icons, when they're shown
+    icons: Option<i32>,
+    /// The workspace whose window list is up
+    hovered: Option<usize>,
+    /// Counts pointer moves, so a conceal can tell it's stale
+    hover: usize,
 }
 
-pub struct IndicatorWidgets {
-    indicators: Vec<gtk::Box>,
+/// The widgets of one workspace, along with the workspace they show
+struct Indicator {
+    root: gtk::Box,
+    /// Lists the windows, when their icons are shown
+    preview: Option<gtk::Popover>,
+    slot: Option<Slot>,
 }
 
-impl SimpleComponent for OpenIndicator {
-    type Init = ();
-    type Input = u16;
-    type Output = ();
-    type Root = gtk::Box;
-    type Widgets = IndicatorWidgets;
+impl Indicator {
+    fn dot() -> Self {
+        Self {
+            root: gtk::Box::new(Orientation::Horizontal, 0),
+            preview: None,
+            slot: None,
+        }
+    }
 
-    fn init(
-        _: Self::Init,
-        root: Self::Root,
-        sender: ComponentSender<Self>,
-    ) -> ComponentParts<Self> {
-        thread::spawn(move || {
-            let mut listener = EventListener::new();
+    /// A workspace showing its windows, which goes to it when clicked and
+    /// lists them on hover
+    fn windows(
+        index: usize,
+        orientation: Orientation,
+        sender: &ComponentSender<OpenIndicator>,
+    ) -> Self {
+        let root = gtk::Box::new(orientation, 2);
+
+        let preview = gtk::Popover::builder()
+            .autohide(false)
+            .position(match orientation {
+                Orientation::Vertical => PositionType::Right,
+                _ => PositionType::Bottom,
+            })
+            .css_classes(["workspace-preview"])
+            .build();
+        preview.set_parent(&root);
 
-            listener.register::<event::OpenWindow>(clone!(
+        let click = GestureClick::builder().button(gdk::BUTTON_PRIMARY).build();
+        click.connect_released(clone!(
+            #[strong]
+            sender,
+            move |_, _, _, _| {
+                let _ = sender.output(Output::Go(index));
+            }
+        ));
+        root.add_controller(click);
+
+        for widget in [root.upcast_ref::<gtk::Widget>(), preview.upcast_ref()] {
+            let motion = EventControllerMotion::new();
+            motion.connect_enter(clone!(
                 #[strong]
                 sender,
-                move |_| sender.input(calculate())
+                move |_, _, _| sender.input(Message::Hover(Some(index)))
             ));
-
-            listener.register::<event::CloseWindow>(clone!(
+            motion.connect_leave(clone!(
                 #[strong]
                 sender,
-                move |_| sender.input(calculate())
+                move |_| sender.input(Message::Hover(None))
             ));
+            widget.add_controller(motion);
+        }
+
+        Self {
+            root,
+            preview: Some(preview),
+            slot: None,
+        }
+    }
+
+    /// Show the icons of `slot`'s windows, or its name when there are none,
+    /// and list them in the preview
+    fn show_windows(&mut self, slot: &Slot, size: i32, sender: &ComponentSender<OpenIndicator>) {
+        let Some(preview) = &self.preview else {
+            return;
+        };
+
+        if self.slot.as_ref() == Some(slot) {
+            return;
+        }
+
+        while let Some(child) = self.root.first_child() {
+            self.root.remove(&child);
+        }
+
+        if slot.clients.is_empty() {
+            let label = gtk::Label::new(Some(&slot.name));
+            label.set_css_classes(css!["name"]);
+            self.root.append(&label);
+        }
+
+        let list = gtk::Box::new(Orientation::Vertical, 0);
+
+        for window in &slot.clients {
+            let icon = gtk::Image::from_gicon(&desktop::icon(&window.class));
+            icon.set_pixel_size(size);
+            icon.set_tooltip_text(Some(&window.title));
+            self.root.append(&icon);
+
+            let row = gtk::Box::new(Orientation::Horizontal, 6);
+            let icon = gtk::Image::from_gicon(&desktop::icon(&window.class));
+            icon.set_pixel_size(size);
+            row.append(&icon);
+
+            let title = gtk::Label::new(Some(&window.title));
+            title.set_max_width_chars(30);
+            title.set_ellipsize(EllipsizeMode::End);
+            row.append(&title);
 
-            listener.register::<event::MoveWindow>(clone!(
+            let button = gtk::Button::new();
+            button.set_css_classes(css!["flat"]);
+            button.set_child(Some(&row));
+            button.connect_clicked(clone!(
                 #[strong]
                 sender,
-                move |_| sender.input(calculate())
+                #[strong(rename_to = address)]
+                window.address,
+                move |_| {
+                    let _ = sender.output(Output::Focus(address.clone()));
+                }
             ));
+            list.append(&button);
+        }
+
+        preview.set_child(Some(&list));
+        self.slot = Some(slot.clone());
+    }
+}
 
-            debug!("Watching for window changes");
-            listener.listen().unwrap();
-        });
+pub struct IndicatorWidgets {
+    root: gtk::Box,
+    indicators: Vec<Indicator>,
+}
 
-        let mask = calculate();
+impl SimpleComponent for OpenIndicator {
+    /// The workspaces, the index of the active one, which way they run and
+    /// the size of the window icons, if they're shown
+    type Init = (Vec<Slot>, Option<usize>, Orientation, Option<i32>);
+    type Input = Message;
+    type Output = Output;
+    type Root = gtk::Box;
+    type Widgets = IndicatorWidgets;
 
-        let mut indicators = vec![];
-        for i in 0..10 {
-            let indicator = gtk::Box::builder().css_classes(cname(mask, i)).build();
+    fn init(
+        (slots, active, orientation, icons): Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        root.set_orientation(orientation);
 
-            root.append(&indicator);
-            indicators.push(indicator);
-        }
+        let model = OpenIndicator {
+            slots,
+            active,
+            icons,
+            hovered: None,
+            hover: 0,
+        };
+        let mut widgets = IndicatorWidgets {
+            root,
+            indicators: Vec::new(),
+        };
 
-        let model = OpenIndicator { mask: 0 };
-        let widgets = IndicatorWidgets { indicators };
+        model.update_view(&mut widgets, sender);
 
         ComponentParts { model, widgets }
     }
@@ -84,13 +224,89 @@ impl SimpleComponent for OpenIndicator {
         gtk::Box::builder().spacing(6).build()
     }
 
-    fn update(&mut self, message: Self::Input, _: ComponentSender<Self>) {
-        self.mask = message;
+    fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
+        match message {
+            Message::Slots(slots) => self.slots = slots,
+            Message::Active(active) => self.active = active,
+            Message::Hover(Some(index)) => {
+                self.hover += 1;
+                self.hovered = Some(index);
+            }
+            Message::Hover(None) => {
+                self.hover += 1;
+                let hover = self.hover;
+
+                glib::timeout_add_local_once(PREVIEW_DELAY, move || {
+                    sender.input(Message::Conceal(hover));
+                });
+            }
+            Message::Conceal(hover) => {
+                if hover == self.hover {
+                    self.hovered = None;
+                }
+            }
+        }
+    }
+
+    fn update_view(&self, widgets: &mut Self::Widgets, sender: ComponentSender<Self>) {
+        while widgets.indicators.len() > self.slots.len() {
+            let indicator = widgets.indicators.pop().unwrap();
+
+            if let Some(preview) = &indicator.preview {
+                preview.unparent();
+            }
+
+            widgets.root.remove(&indicator.root);
+        }
+
+        while widgets.indicators.len() < self.slots.len() {
+            let indicator = match self.icons {
+                Some(_) => Indicator::windows(
+                    widgets.indicators.len(),
+                    widgets.root.orientation(),
+                    &sender,
+                ),
+                None => Indicator::dot(),
+            };
+
+            widgets.root.append(&indicator.root);
+            widgets.indicators.push(indicator);
+        }
+
+        for (index, (slot, indicator)) in self.slots.iter().zip(&mut widgets.indicators).enumerate()
+        {
+            let active = self.active == Some(index);
+            indicator
+                .root
+                .set_css_classes(cname(slot, active, self.icons.is_some()).as_slice());
+
+            let Some(size) = self.icons else {
+                indicator
+                    .root
+                    .set_tooltip_text(slot.is_named().then_some(slot.name.as_str()));
+                continue;
+            };
+
+            indicator.show_windows(slot, size, &sender);
+
+            let Some(preview) = &indicator.preview else {
+                continue;
+            };
+
+            let shown = self.hovered == Some(index) && !slot.clients.is_empty();
+
+            if preview.is_visible() != shown {
+                match shown {
+                    true => preview.popup(),
+                    false => preview.popdown(),
+                }
+            }
+        }
     }
 
-    fn update_view(&self, widgets: &mut Self::Widgets, _: ComponentSender<Self>) {
-        for (i, w) in widgets.indicators.iter_mut().enumerate() {
-            w.set_css_classes(cname(self.mask, i).as_slice());
+    fn shutdown(&mut self, widgets: &mut Self::Widgets, _: relm4::Sender<Self::Output>) {
+        for preview in widgets.indicators.iter().filter_map(|i| i.preview.as_ref()) {
+            preview.unparent();
         }
     }
 }
@@ -1,178 +1,177 @@
-use std::{
-    f64,
-    sync::{
-        atomic::{self, AtomicPtr, AtomicU8, AtomicUsize},
-        Arc,
-    },
-};
+use std::{cell::Cell, f64, rc::Rc};
 
-use hyprland::{
-    command::{ActiveWorkspace, Executor},
-    event,
-};
+use gnyprland_config::AnimationConfig;
 use relm4::gtk::{
-    glib::{timeout_add, translate::FromGlibPtrNone, ControlFlow},
-    DrawingArea,
+    cairo::{Context, Matrix},
+    DrawingArea, Orientation,
 };
 
-use crate::prelude::*;
+use crate::{animation::Animation, prelude::*};
 
-const ANIM_DURATION: f64 = 0.1;
-const FRAME_TIME: f64 = 0.010;
+/// Where the slider draws each workspace. The dots are as thick as the
+/// slider, which CSS sets with `min-height` (or `min-width` when vertical),
+/// and spread out over its length, which follows the indicator next to it.
+#[derive(Clone, Copy, Debug)]
+struct Geometry {
+    dot: f64,
+    step: f64,
+}
+
+impl Geometry {
+    fn new(area: &DrawingArea, orientation: Orientation, count: usize) -> Self {
+        let (width, height) = (area.width() as f64, area.height() as f64);
+        let (length, dot) = match orientation {
+            Orientation::Vertical => (height, width),
+            _ => (width, height),
+        };
+
+        let step = match count {
+            0 | 1 => 0.0,
+            count => (length - dot).max(0.0) / (count - 1) as f64,
+        };
+
+        Self { dot, step }
+    }
 
-fn translate(a: f64, b: f64, t: f64) -> f64 {
-    t * t * (3.0 - 2.0 * t) * (b - a) + a
+    /// Where the dot of the workspace at `position` is centered along the
+    /// slider
+    fn center(&self, position: f64) -> f64 {
+        self.dot / 2.0 + position * self.step
+    }
 }
 
-fn recalculate_active() -> u8 {
-    let active = Executor::command::<ActiveWorkspace>().unwrap();
+/// The index of the workspace whose dot is nearest to `(x, y)` on the slider
+pub fn index_at(
+    area: &DrawingArea,
+    orientation: Orientation,
+    (x, y): (f64, f64),
+    count: usize,
+) -> Option<usize> {
+    if count == 0 {
+        return None;
+    }
+
+    let geometry = Geometry::new(area, orientation, count);
+    let along = match orientation {
+        Orientation::Vertical => y,
+        _ => x,
+    };
 
-    trace!("New active workspace: {}", active.name);
-    str::parse(&active.name).unwrap()
+    let end = geometry.center((count - 1) as f64) + geometry.dot / 2.0;
+
+    if !(0.0..=end).contains(&along) {
+        return None;
+    }
+
+    let index = match geometry.step > 0.0 {
+        true => ((along - geometry.center(0.0)) / geometry.step)
+            .round()
+            .max(0.0) as usize,
+        false => 0,
+    };
+
+    Some(index.min(count - 1))
 }
 
-pub struct DrawData {
-    last: AtomicU8,
-    current: AtomicU8,
-    nth: AtomicUsize,
+/// Draw the slider as a track from the first to the last workspace, with
+/// the active one as a dot set apart from it, in the CSS `color`
+fn draw(area: &DrawingArea, ctx: &Context, orientation: Orientation, count: usize, position: f64) {
+    if orientation == Orientation::Vertical {
+        // draw across, and mirror it into place
+        ctx.transform(Matrix::new(0.0, 1.0, 1.0, 0.0, 0.0, 0.0));
+    }
+
+    let geometry = Geometry::new(area, orientation, count);
+    let radius = geometry.dot / 2.0;
+
+    ctx.set_source_color(&area.color());
+
+    let dot = |center: f64| {
+        ctx.arc(center, radius, radius, 0.0, f64::consts::TAU);
+        ctx.fill().unwrap();
+    };
+
+    let prev = geometry.center(position - 1.0);
+    let next = geometry.center(position + 1.0);
+
+    let first = geometry.center(0.0);
+    let last_index = count.saturating_sub(1) as f64;
+    let last = geometry.center(last_index);
+
+    // the active workspace, and the ends of the track on either side of it
+    dot(geometry.center(position));
+    dot(prev);
+    dot(next);
+
+    // fill in the track up to the ends
+    if position > 1.0 {
+        dot(first);
+        ctx.rectangle(first, 0.0, prev - first, geometry.dot);
+        ctx.fill().unwrap();
+    }
+
+    if position < last_index - 1.0 {
+        dot(last);
+        ctx.rectangle(next, 0.0, last - next, geometry.dot);
+        ctx.fill().unwrap();
+    }
 }
 
-pub struct ActiveSlider {
-    draw_data: Arc<DrawData>,
+#[derive(Clone, Debug)]
+pub enum Message {
+    /// Slide to the workspace at index `active` out of `count`
+    Move { active: usize, count: usize },
 }
 
-pub struct ActiveWorkspaceWidgets {
-    root: gtk::DrawingArea,
+pub struct ActiveSlider {
+    /// Index of the active workspace, animated between workspaces
+    position: Animation,
+    count: Rc<Cell<usize>>,
 }
 
+pub struct ActiveSliderWidgets {}
+
 impl SimpleComponent for ActiveSlider {
-    type Init = ();
-    type Input = u8;
+    /// Index of the active workspace, how many there are, which way the
+    /// slider runs and how it animates
+    type Init = (usize, usize, Orientation, AnimationConfig);
+    type Input = Message;
     type Output = ();
     type Root = gtk::DrawingArea;
-    type Widgets = ActiveWorkspaceWidgets;
+    type Widgets = ActiveSliderWidgets;
 
     fn init_root() -> Self::Root {
         gtk::DrawingArea::builder().css_classes(["slider"]).build()
     }
 
     fn init(
-        _: Self::Init,
+        (active, count, orientation, animation): Self::Init,
         root: Self::Root,
-        sender: ComponentSender<Self>,
+        _sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
-        let current = recalculate_active();
-        let draw_data = Arc::new(DrawData {
-            last: AtomicU8::new(current - 1),
-            current: AtomicU8::new(current - 1),
-            nth: AtomicUsize::new(0),
-        });
-        let widgets = ActiveWorkspaceWidgets { root };
         let model = ActiveSlider {
-            draw_data: Arc::clone(&draw_data),
+            position: Animation::new(&root, active as f64, animation),
+            count: Rc::new(Cell::new(count)),
         };
 
-        thread::spawn(move || {
-            let mut listener = EventListener::new();
-
-            listener.register::<event::Workspace>(clone!(
-                #[strong]
-                sender,
-                move |_| sender.input(recalculate_active()),
-            ));
-
-            debug!("Watching for active workspace changes");
-            listener.listen().unwrap()
-        });
-
-        widgets.root.set_draw_func(move |_, ctx, _w, _h| {
-            // step 0. reuse
-            let wksp_to_draw = |position: f64| 3.0 + (position * 12.0);
-
-            // step 1. calculate the position of the dot
-            let last = draw_data.last.load(atomic::Ordering::Relaxed);
-            let current = draw_data.current.load(atomic::Ordering::Relaxed);
-            let nth = draw_data.nth.fetch_add(1, atomic::Ordering::SeqCst) + 1;
-
-            let elapsed = (nth as f64 * FRAME_TIME) / ANIM_DURATION;
-            let dot_pos = translate(last as f64, current as f64, elapsed.clamp(0.0, 1.0));
-            let dot_pos_px = wksp_to_draw(dot_pos);
-
-            // step 1.1. make all other calculations before drawing
-            let prev = wksp_to_draw(dot_pos - 1.0);
-            let next = wksp_to_draw(dot_pos + 1.0);
-
-            let first = wksp_to_draw(0.0);
-            let last = wksp_to_draw(9.0);
-
-            // step 2. draw a dot at the calculated position
-            ctx.arc(dot_pos_px, 3.0, 3.0, 0.0, f64::consts::TAU);
-            ctx.set_source_rgba(0.9, 0.9, 0.9, 1.0);
-            ctx.fill().unwrap();
-
-            // step 3. draw a dot to the left and the right of the current position
-            ctx.arc(prev, 3.0, 3.0, 0.0, f64::consts::TAU);
-            ctx.set_source_rgba(0.9, 0.9, 0.9, 1.0);
-            ctx.fill().unwrap();
-
-            ctx.arc(next, 3.0, 3.0, 0.0, f64::consts::TAU);
-            ctx.set_source_rgba(0.9, 0.9, 0.9, 1.0);
-            ctx.fill().unwrap();
-
-            // step 4. draw a dot at the ends, and fill in gaps
-            if dot_pos > 1.0 {
-                ctx.arc(first, 3.0, 3.0, 0.0, f64::consts::TAU);
-                ctx.set_source_rgba(0.9, 0.9, 0.9, 1.0);
-                ctx.fill().unwrap();
-
-                ctx.rectangle(first, 0.0, prev - first, 6.0);
-                ctx.set_source_rgba(0.9, 0.9, 0.9, 1.0);
-                ctx.fill().unwrap();
-            }
-
-            if dot_pos < 8.0 {
-                ctx.arc(last, 3.0, 3.0, 0.0, f64::consts::TAU);
-                ctx.set_source_rgba(0.9, 0.9, 0.9, 1.0);
-                ctx.fill().unwrap();
-
-                ctx.rectangle(next, 0.0, last - next, 6.0);
-                ctx.set_source_rgba(0.9, 0.9, 0.9, 1.0);
-                ctx.fill().unwrap();
-            }
-        });
-
-        ComponentParts { model, widgets }
+        root.set_draw_func(clone!(
+            #[strong(rename_to = position)]
+            model.position,
+            #[strong(rename_to = count)]
+            model.count,
+            move |area, ctx, _, _| draw(area, ctx, orientation, count.get(), position.value())
+        ));
+
+        ComponentParts {
+            model,
+            widgets: ActiveSliderWidgets {},
+        }
     }
 
     fn update(&mut self, message: Self::Input, _: ComponentSender<Self>) {
-        self.draw_data.last.store(
-            self.draw_data.current.load(atomic::Ordering::Relaxed),
-            atomic::Ordering::Relaxed,
-        );
-        self.draw_data
-            .current
-            .store(message - 1, atomic::Ordering::Relaxed);
-    }
-
-    fn update_view(&self, widgets: &mut Self::Widgets, _: ComponentSender<Self>) {
-        let area = widgets.root.clone();
-        let draw_data = Arc::clone(&self.draw_data);
-        let area_ptr = AtomicPtr::new(area.as_ptr());
-
-        self.draw_data.nth.store(0, atomic::Ordering::Relaxed);
-
-        timeout_add(Duration::from_secs_f64(FRAME_TIME), move || {
-            // safety: I shouldn't have to do this, fuck you.
-            let ptr = area_ptr.load(atomic::Ordering::Relaxed);
-            let area = unsafe { DrawingArea::from_glib_none(ptr) };
-
-            area.queue_draw();
+        let Message::Move { active, count } = message;
 
-            if draw_data.nth.load(atomic::Ordering::SeqCst) as f64 >= ANIM_DURATION / FRAME_TIME {
-                ControlFlow::Break
-            } else {
-                ControlFlow::Continue
-            }
-        });
+        self.count.set(count);
+        self.position.animate_to(active as f64);
     }
 }
@@ -2,7 +2,9 @@ use relm4::Worker;
 use zbus::{connection, Connection};
 
 use super::{daemon::NotificationDaemon, model::CloseReason, UiMessage};
-use crate::prelude::*;
+use crate::{prelude::*, shutdown};
+
+const NAME: &str = "org.freedesktop.Notifications";
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum DbusMessage {
@@ -23,13 +25,21 @@ impl Worker for NotificationDbusWorker {
         let daemon = NotificationDaemon::new(sender.output_sender().clone());
         let connection = smol::block_on::<Result<_, zbus::Error>>(async move {
             connection::Builder::session()?
-                .name("org.freedesktop.Notifications")?
+                .name(NAME)?
                 .serve_at("/org/freedesktop/Notifications", daemon)?
                 .build()
                 .await
         })
         .unwrap();
 
+        let conn = connection.clone();
+        shutdown::on_shutdown("notifications", move || {
+            match smol::block_on(conn.release_name(NAME)) {
+                Ok(_) => debug!("Released {NAME}"),
+                Err(e) => error!("Failed to release {NAME}: {e}"),
+            }
+        });
+
         Self { connection }
     }
 
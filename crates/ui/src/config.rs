@@ -0,0 +1,64 @@
+use std::path::PathBuf;
+
+use gnyprland_config::{error::ConfigError, Config};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use smol::{channel, Timer};
+
+use crate::prelude::*;
+
+/// Editors tend to save in several steps, so wait for things to settle before
+/// reading the file
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// Watch the config file and hand every successfully or unsuccessfully parsed
+/// version to `reload`. Deleting the file keeps the current config.
+pub fn begin_watch(path: PathBuf, reload: impl Fn(Result<Config, ConfigError>) + Send + 'static) {
+    // watch the directory, since saving often replaces the file altogether
+    let Some(dir) = path.parent().map(PathBuf::from) else {
+        return;
+    };
+
+    smol::spawn(async move {
+        let (tx, rx) = channel::unbounded();
+        let mut watcher = RecommendedWatcher::new(
+            move |res| smol::block_on(tx.send(res)).unwrap(),
+            Default::default(),
+        )
+        .unwrap();
+
+        if let Err(e) = watcher.watch(&dir, RecursiveMode::NonRecursive) {
+            warn!("Not watching {} for config changes: {e}", dir.display());
+            return;
+        }
+
+        debug!("Watching {} for config changes", path.display());
+
+        loop {
+            let Ok(Ok(event)) = rx.recv().await else {
+                continue;
+            };
+
+            if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                continue;
+            }
+
+            if !event
+                .paths
+                .iter()
+                .any(|p| p.file_name() == path.file_name())
+            {
+                continue;
+            }
+
+            Timer::after(DEBOUNCE).await;
+            while rx.try_recv().is_ok() {}
+
+            if !path.exists() {
+                continue;
+            }
+
+            reload(Config::load(&path));
+        }
+    })
+    .detach();
+}
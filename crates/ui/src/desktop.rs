@@ -0,0 +1,145 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use relm4::gtk::{
+    gdk,
+    gio::{self, DesktopAppInfo},
+};
+
+use crate::prelude::*;
+
+/// Shown for windows without a desktop entry, or whose icon the theme lacks
+const FALLBACK_ICON: &str = "application-x-executable";
+
+/// Shown when no window has focus
+const DESKTOP_ICON: &str = "user-desktop";
+
+thread_local! {
+    /// Lookups by window class, including the ones that found nothing
+    static ENTRIES: RefCell<HashMap<String, Option<DesktopAppInfo>>> =
+        RefCell::new(HashMap::new());
+
+    /// Resolved icons by window class
+    static ICONS: RefCell<HashMap<String, gio::Icon>> = RefCell::new(HashMap::new());
+
+    /// Only signals while someone holds on to it
+    static MONITOR: gio::AppInfoMonitor = gio::AppInfoMonitor::get();
+}
+
+/// The desktop file id without `.desktop`
+fn id_stem(info: &DesktopAppInfo) -> Option<String> {
+    let id = info.id()?;
+    Some(id.strip_suffix(".desktop").unwrap_or(&id).to_string())
+}
+
+/// Whether `info` looks like the entry of windows with `class` when neither
+/// names the other exactly
+fn resembles(info: &DesktopAppInfo, class: &str) -> bool {
+    // Flatpak ids are reverse DNS, like org.mozilla.firefox for `firefox`
+    let flatpak = id_stem(info)
+        .and_then(|id| Some(id.rsplit_once('.')?.1.to_string()))
+        .is_some_and(|name| name.eq_ignore_ascii_case(class));
+
+    // Electron apps tend to use their executable's name as the class
+    let executable = info
+        .executable()
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.eq_ignore_ascii_case(class));
+
+    flatpak || executable
+}
+
+/// Find the desktop entry of an app from its window class: the entry named
+/// after the class, as is or in lowercase, or else one whose
+/// `StartupWMClass` is the class, or else one that [resembles] it
+fn find(class: &str) -> Option<DesktopAppInfo> {
+    let entry = DesktopAppInfo::new(&format!("{class}.desktop"))
+        .or_else(|| DesktopAppInfo::new(&format!("{}.desktop", class.to_lowercase())));
+
+    if entry.is_some() {
+        return entry;
+    }
+
+    let all = gio::AppInfo::all()
+        .into_iter()
+        .filter_map(|info| info.downcast::<DesktopAppInfo>().ok())
+        .collect::<Vec<_>>();
+
+    let wm_class = all.iter().find(|info| {
+        info.startup_wm_class()
+            .is_some_and(|c| c.eq_ignore_ascii_case(class))
+    });
+
+    wm_class
+        .or_else(|| all.iter().find(|info| resembles(info, class)))
+        .cloned()
+}
+
+/// The desktop entry for windows of `class`, looked up once per class
+pub fn entry(class: &str) -> Option<DesktopAppInfo> {
+    if class.is_empty() {
+        return None;
+    }
+
+    ENTRIES.with_borrow_mut(|entries| {
+        entries
+            .entry(class.to_string())
+            .or_insert_with(|| {
+                let entry = find(class);
+                trace!(
+                    "Desktop entry for {class}: {:?}",
+                    entry.as_ref().and_then(|e| e.id())
+                );
+                entry
+            })
+            .clone()
+    })
+}
+
+/// The name an app goes by in its desktop entry
+pub fn name(class: &str) -> Option<String> {
+    entry(class).map(|entry| entry.name().to_string())
+}
+
+/// Whether `icon` can be drawn, from the icon theme or from a file
+fn available(icon: &gio::Icon) -> bool {
+    if let Some(file) = icon.downcast_ref::<gio::FileIcon>() {
+        return file.file().path().is_some_and(|path| path.exists());
+    }
+
+    gtk::IconTheme::for_display(&gdk::Display::default().unwrap()).has_gicon(icon)
+}
+
+/// The icon of the app behind windows of `class`, falling back to a generic
+/// one. Resolved once per class until the icon theme or apps change.
+pub fn icon(class: &str) -> gio::Icon {
+    ICONS.with_borrow_mut(|icons| {
+        icons
+            .entry(class.to_string())
+            .or_insert_with(|| {
+                if class.is_empty() {
+                    return gio::ThemedIcon::new(DESKTOP_ICON).upcast();
+                }
+
+                entry(class)
+                    .and_then(|entry| entry.icon())
+                    .filter(available)
+                    .unwrap_or_else(|| gio::ThemedIcon::new(FALLBACK_ICON).upcast())
+            })
+            .clone()
+    })
+}
+
+/// Forget every lookup when apps are installed or removed, or the icon theme
+/// changes
+pub fn watch() {
+    let clear = || {
+        debug!("Clearing desktop entry and icon caches");
+        ENTRIES.with_borrow_mut(HashMap::clear);
+        ICONS.with_borrow_mut(HashMap::clear);
+    };
+
+    MONITOR.with(|monitor| monitor.connect_changed(move |_| clear()));
+    gtk::IconTheme::for_display(&gdk::Display::default().unwrap())
+        .connect_changed(move |_| clear());
+}
@@ -1,9 +1,11 @@
 #![feature(error_generic_member_access, decl_macro, iter_array_chunks)]
 
 extern crate cfg_if;
+extern crate gnyprland_config;
 extern crate gnyprland_relay;
 extern crate gtk4_layer_shell;
 extern crate hyprland;
+extern crate libc;
 extern crate log;
 extern crate map_macro;
 extern crate notify;
@@ -12,18 +14,30 @@ extern crate smol;
 extern crate thiserror;
 extern crate zbus;
 
+mod animation;
+mod app;
 mod bar;
 mod center_menu;
+mod config;
 mod css;
+mod desktop;
 mod overlays;
 mod prelude;
+mod shutdown;
+pub mod systemd;
 
-use bar::Bar;
+use std::path::PathBuf;
+
+use app::App;
+use gnyprland_config::Config;
 use gnyprland_relay::message::IpcReceiver;
 use relm4::RelmApp;
 
-pub fn start(receiver: IpcReceiver) {
-    let bar = RelmApp::new("gnyprland.bar");
-    bar.allow_multiple_instances(false);
-    bar.run::<Bar>(receiver);
+/// Run the bar until it shuts down. `config_path` is watched, so that edits to
+/// it apply without a restart.
+pub fn start(receiver: IpcReceiver, config: Config, config_path: PathBuf) {
+    // the app's own window stays hidden, the bars get one each
+    let app = RelmApp::new("gnyprland.bar").visible_on_activate(false);
+    app.allow_multiple_instances(false);
+    app.run::<App>((receiver, config, config_path));
 }
@@ -1,4 +1,7 @@
-use std::sync::{Arc, LazyLock, RwLock};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, LazyLock, RwLock,
+};
 
 use relm4::Sender;
 
@@ -10,29 +13,79 @@ pub enum ActiveOverlay {
 }
 
 impl ActiveOverlay {
-    fn callbacks<'a>() -> &'a RwLock<Vec<Callback>> {
-        static CALLBACKS: LazyLock<Arc<RwLock<Vec<Callback>>>> =
+    /// Keyed by what `on_change` handed out, so they can be removed again
+    fn callbacks<'a>() -> &'a RwLock<Vec<(usize, Callback)>> {
+        static CALLBACKS: LazyLock<Arc<RwLock<Vec<(usize, Callback)>>>> =
             LazyLock::new(|| Arc::new(RwLock::new(Vec::new())));
 
         CALLBACKS.as_ref()
     }
 
+    fn state<'a>() -> &'a RwLock<Option<ActiveOverlay>> {
+        static STATE: RwLock<Option<ActiveOverlay>> = RwLock::new(None);
+
+        &STATE
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ActiveOverlay::Center => "center",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ActiveOverlay> {
+        match name {
+            "center" => Some(ActiveOverlay::Center),
+            _ => None,
+        }
+    }
+
+    pub fn toggle(value: ActiveOverlay) {
+        if Self::current() == Some(value) {
+            Self::set(None);
+        } else {
+            Self::set(Some(value));
+        }
+    }
+
+    /// Send `f` of the overlay to `sender` whenever it changes. Returns the key
+    /// to stop again with [`ActiveOverlay::remove_on_change`].
     pub fn on_change<M: Send + Sync + 'static>(
         sender: &Sender<M>,
         f: fn(Option<ActiveOverlay>) -> M,
-    ) {
+    ) -> usize {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+
         let mut callbacks = Self::callbacks().write().unwrap();
         let sender = sender.clone();
+        let key = NEXT.fetch_add(1, Ordering::Relaxed);
+
+        callbacks.push((
+            key,
+            Arc::new(move |value| {
+                sender.emit(f(value));
+            }),
+        ));
+
+        key
+    }
+
+    pub fn remove_on_change(key: usize) {
+        Self::callbacks()
+            .write()
+            .unwrap()
+            .retain(|(k, _)| *k != key);
+    }
 
-        callbacks.push(Arc::new(move |value| {
-            sender.emit(f(value));
-        }));
+    pub fn current() -> Option<ActiveOverlay> {
+        *Self::state().read().unwrap()
     }
 
     pub fn set(value: Option<ActiveOverlay>) {
+        *Self::state().write().unwrap() = value;
         let callbacks = Self::callbacks().read().unwrap();
 
-        for callback in callbacks.iter() {
+        for (_, callback) in callbacks.iter() {
             callback(value);
         }
     }
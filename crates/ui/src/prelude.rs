@@ -3,7 +3,7 @@ pub use std::{convert::identity, thread, time::Duration};
 pub use cfg_if::cfg_if;
 pub use gtk::{self, glib::clone, prelude::*};
 pub use gtk4_layer_shell::{Edge, Layer, LayerShell};
-pub use hyprland::listener::EventListener;
+pub use hyprland::listener::{EventListener, ListenerHandle};
 pub use log::*;
 pub use map_macro::hash_map;
 pub use relm4::prelude::*;
@@ -0,0 +1,48 @@
+use std::{mem, sync::Mutex};
+
+use relm4::gtk::glib::{self, ControlFlow};
+
+use crate::{prelude::*, systemd};
+
+type Hook = Box<dyn FnOnce() + Send + 'static>;
+
+static HOOKS: Mutex<Vec<(&'static str, Hook)>> = Mutex::new(Vec::new());
+
+/// Register `hook` to run when the bar shuts down. Hooks run in reverse order
+/// of registration, so whatever was set up first is torn down last.
+pub fn on_shutdown(name: &'static str, hook: impl FnOnce() + Send + 'static) {
+    HOOKS.lock().unwrap().push((name, Box::new(hook)));
+}
+
+/// Run every shutdown hook and stop the main loop, which hands control back to
+/// the caller of [`crate::start`]. Calling this twice is harmless.
+pub fn shutdown() {
+    systemd::stopping();
+
+    let hooks = mem::take(&mut *HOOKS.lock().unwrap());
+
+    for (name, hook) in hooks.into_iter().rev() {
+        debug!("Running shutdown hook: {name}");
+        hook();
+    }
+
+    relm4::main_application().quit();
+}
+
+/// Shut down cleanly on SIGTERM, SIGINT and SIGHUP. The handlers run on the
+/// main loop rather than in a signal handler, so they may touch GTK.
+pub fn handle_signals() {
+    let signals = [
+        (libc::SIGTERM, "SIGTERM"),
+        (libc::SIGINT, "SIGINT"),
+        (libc::SIGHUP, "SIGHUP"),
+    ];
+
+    for (signal, name) in signals {
+        glib::unix_signal_add_local(signal, move || {
+            info!("Received {name}, shutting down...");
+            shutdown();
+            ControlFlow::Break
+        });
+    }
+}
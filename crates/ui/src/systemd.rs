@@ -0,0 +1,57 @@
+// Service manager notifications. Without the `systemd` feature, or when not
+// started by systemd, every function here does nothing.
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "systemd")] {
+        use relm4::gtk::glib::{self, ControlFlow};
+        use sd_notify::NotifyState;
+
+        use crate::prelude::*;
+
+        fn notify(state: NotifyState) {
+            if let Err(e) = sd_notify::notify(false, &[state]) {
+                warn!("Failed to notify the service manager: {e}");
+            }
+        }
+    }
+}
+
+/// Tell the service manager that the bar is up
+pub fn ready() {
+    #[cfg(feature = "systemd")]
+    notify(NotifyState::Ready);
+}
+
+/// Tell the service manager that the bar is going away
+pub fn stopping() {
+    #[cfg(feature = "systemd")]
+    notify(NotifyState::Stopping);
+}
+
+/// Set the free-form status shown by `systemctl status`
+#[cfg_attr(not(feature = "systemd"), allow(unused_variables))]
+pub fn status(status: &str) {
+    #[cfg(feature = "systemd")]
+    notify(NotifyState::Status(status));
+}
+
+/// Ping the watchdog from the main loop at half the configured interval, so a
+/// hung UI gets the service restarted
+pub fn start_watchdog() {
+    #[cfg(feature = "systemd")]
+    {
+        let mut usec = 0;
+
+        if !sd_notify::watchdog_enabled(false, &mut usec) {
+            return;
+        }
+
+        let interval = Duration::from_micros(usec / 2);
+        debug!("Pinging the watchdog every {interval:?}");
+
+        glib::timeout_add_local(interval, || {
+            notify(NotifyState::Watchdog);
+            ControlFlow::Continue
+        });
+    }
+}